@@ -0,0 +1,202 @@
+use serde::Serialize;
+
+use super::to_object;
+use crate::{files::normalize_path, Error, Object, Page, Unreact, Value};
+
+/// Key of global variables in template data
+const GLOBAL_KEY: &str = "GLOBAL";
+
+impl<'a> Unreact<'a> {
+    /// Set global variables for templates, replacing all existing global variables
+    ///
+    /// `data` can be an `Object` (see [`object!`](macro.object.html)), or any value which serializes to an object, such as a struct deriving `Serialize`
+    ///
+    /// Returns `Err` if data could not be serialized, or is not an object
+    ///
+    /// Scoped global variables (see [`scope_globals`](struct.Unreact.html#method.scope_globals)) are not changed
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use unreact::prelude::*;
+    /// # fn main() -> Result<(), Error> {
+    /// Unreact::new(Config::default(), false, "https://example.com")?
+    ///     // Index page
+    ///     .index("page", object! {})?
+    ///     // Globalize does not need to be ran before routes
+    ///     .globalize(object! {smiley: "(^_^)"})?
+    ///     // Compiles with a smiley face replacing `{{GLOBAL.smiley}}`
+    ///     .run()
+    /// # }
+    /// ```
+    pub fn globalize(&mut self, data: impl Serialize) -> Result<&mut Self, Error> {
+        self.globals = to_object(data, "globals")?;
+        Ok(self)
+    }
+
+    /// Set a single global variable, replacing any existing value with the same key
+    ///
+    /// `value` can be any serializable value
+    ///
+    /// Returns `Err` if value could not be serialized
+    pub fn set_global(&mut self, key: &str, value: impl Serialize) -> Result<&mut Self, Error> {
+        let value = try_unwrap!(
+            serde_json::to_value(value),
+            else Err(err) => return fail!(InvalidData, format!("global '{key}'"), err.to_string()),
+        );
+        self.globals.insert(key.to_string(), value);
+        Ok(self)
+    }
+
+    /// Merge global variables into existing global variables
+    ///
+    /// Objects are merged deeply, so separate parts of an app can each add keys to the same object.
+    /// Any other values (including arrays) replace the existing value
+    ///
+    /// Returns `Err` if data could not be serialized, or is not an object
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use unreact::prelude::*;
+    /// # fn main() -> Result<(), Error> {
+    /// Unreact::new(Config::default(), false, "https://example.com")?
+    ///     .globalize(object! {site: object! {name: "Blog"}})?
+    ///     // `{{GLOBAL.site.name}}` is kept
+    ///     .merge_globals(object! {site: object! {author: "Me"}})?
+    ///     .index("page", object! {})?
+    ///     .run()
+    /// # }
+    /// ```
+    pub fn merge_globals(&mut self, data: impl Serialize) -> Result<&mut Self, Error> {
+        let data = to_object(data, "globals")?;
+        merge_objects(&mut self.globals, data);
+        Ok(self)
+    }
+
+    /// Set global variables for routes under a path prefix, merged over app global variables
+    ///
+    /// - `prefix`: Route path, such as `docs`, which matches `docs` and `docs/...` routes (but not `docs-old`)
+    ///
+    /// Calling again with the same prefix merges deeply into the existing scoped variables
+    ///
+    /// ## Precedence
+    ///
+    /// The `GLOBAL` object given to a template is merged deeply in this order, with later values taking precedence:
+    ///
+    /// 1. App global variables (`globalize`, `set_global`, `merge_globals`)
+    /// 2. Scoped global variables, with shorter prefixes first (`docs` before `docs/api`)
+    /// 3. The `GLOBAL` key of the route data, if it is an object
+    ///
+    /// Returns `Err` if data could not be serialized, or is not an object
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use unreact::prelude::*;
+    /// # fn main() -> Result<(), Error> {
+    /// Unreact::new(Config::default(), false, "https://example.com")?
+    ///     .globalize(object! {sidebar: "main"})?
+    ///     // Routes under `docs/` use a different sidebar
+    ///     .scope_globals("docs", object! {sidebar: "docs"})?
+    ///     .route("docs/install", "page", object! {})?
+    ///     // Route data overrides both
+    ///     .route("docs/legacy", "page", object! {GLOBAL: object! {sidebar: "legacy"}})?
+    ///     .run()
+    /// # }
+    /// ```
+    pub fn scope_globals(
+        &mut self,
+        prefix: &str,
+        data: impl Serialize,
+    ) -> Result<&mut Self, Error> {
+        let prefix = normalize_path(prefix);
+        let data = to_object(data, &format!("globals of '{prefix}'"))?;
+        merge_objects(self.scoped_globals.entry(prefix).or_default(), data);
+        Ok(self)
+    }
+
+    /// Get global variables for a route, with scoped variables and route data merged in order of precedence
+    ///
+    /// See [`scope_globals`](struct.Unreact.html#method.scope_globals)
+    pub(super) fn globals_for(&self, route: &str, page: &Page) -> Object {
+        let mut globals = self.globals.clone();
+
+        // Sorted by prefix, so shorter prefixes are merged first
+        let route = normalize_path(route);
+        for (prefix, data) in &self.scoped_globals {
+            if is_under_prefix(&route, prefix) {
+                merge_objects(&mut globals, data.clone());
+            }
+        }
+
+        if let Page::Template { data, .. } = page {
+            if let Some(Value::Object(data)) = data.get(GLOBAL_KEY) {
+                merge_objects(&mut globals, data.clone());
+            }
+        }
+
+        globals
+    }
+}
+
+/// Check if a route is the prefix, or inside the prefix folder
+///
+/// Empty prefix matches every route
+fn is_under_prefix(route: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || route
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Merge an object into another object deeply
+///
+/// Objects in both are merged recursively, otherwise values from `other` replace values in `base`
+fn merge_objects(base: &mut Object, other: Object) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Object(base)), Value::Object(value)) => merge_objects(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_objects_works() {
+        let mut base = object! {
+            site: object! {name: "Blog", links: ["a", "b"]},
+            smiley: "(^_^)",
+        };
+        merge_objects(
+            &mut base,
+            object! {
+                site: object! {author: "Me", links: ["c"]},
+                smiley: Value::Null,
+            },
+        );
+
+        assert_eq!(
+            base,
+            object! {
+                site: object! {name: "Blog", author: "Me", links: ["c"]},
+                smiley: Value::Null,
+            }
+        );
+    }
+
+    #[test]
+    fn is_under_prefix_works() {
+        assert!(is_under_prefix("docs", "docs"));
+        assert!(is_under_prefix("docs/api", "docs"));
+        assert!(is_under_prefix("about", ""));
+        assert!(!is_under_prefix("docs-old", "docs"));
+        assert!(!is_under_prefix("", "docs"));
+    }
+}
@@ -1,19 +1,37 @@
 /// All route creation implementations for `Unreact` struct
+mod globals;
 mod routes;
 
-use std::fs;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Instant,
+};
 
 use handlebars::Handlebars;
+use serde::Serialize;
 
+#[cfg(feature = "compress")]
+use crate::convert::{compress_file, compress_outputs, is_compressible};
 use crate::{
-    convert::{register_inbuilt, register_templates, render_page, scss_to_css},
-    files::{check_source_folders, clean_build_dir, read_folder_recurse},
-    Config, Error, Object, Port, RouteMap, Unreact, DEV_BUILD_DIR,
+    convert::{
+        base_path_links, check_links, inline_critical_css, is_partial, load_paths, process_css,
+        purge_css, register_inbuilt, register_templates, render_page, scss_to_css, source_map,
+        strip_critical_markers, style_path, style_syntax, UsedSelectors,
+    },
+    files::{
+        clean_build_dir, copy_file, get_filename, list_folder_recurse, normalize_path,
+        remove_build_dir, swap_build_dir, temp_build_dir, write_file, FileFilter, Source,
+    },
+    Args, BuildReport, Command, Config, CopyList, Error, FileReport, LinkCheck, Object, OutputMap,
+    Page, RouteBuilder, RouteMap, RouteReport, Unreact, Value, DEV_BUILD_DIR,
 };
 
 impl<'a> Unreact<'a> {
     /// Create a new empty `Unreact` app
     ///
+    /// Returns `Err` if a source folder does not exist, or config is invalid (See [`Config::validate`]).
+    /// Config is always validated, including configs created in code
+    ///
     /// ## Parameters
     ///
     /// - `config`: Configuration for the app (See [`Config`])
@@ -59,7 +77,7 @@ impl<'a> Unreact<'a> {
     ///     // Set a global variable named 'smiley'
     ///     app.globalize(object! {
     ///         smiley: "(^_^)"
-    ///     });
+    ///     })?;
     ///
     ///     // Some routes
     ///     app.index("page", object! {message: "World"})?
@@ -74,60 +92,228 @@ impl<'a> Unreact<'a> {
     ///     Ok(())
     /// }
     /// ```
-    pub fn new(mut config: Config, is_dev: bool, url: &str) -> Result<Self, Error> {
+    pub fn new(config: Config, is_dev: bool, url: &str) -> Result<Self, Error> {
+        Self::init(config, is_dev, url, Source::Disk)
+    }
+
+    /// Create a new empty `Unreact` app, with source files from memory or disk
+    ///
+    /// Same as `Unreact::new`, but templates, styles, and public files are read from `source` (see [`Source`]).
+    /// *Dev mode* is not active, as the *dev server* reads files from disk
+    ///
+    /// ## Parameters
+    ///
+    /// - `config`: Configuration for the app (See [`Config`])
+    /// - `url`: The url that should be given to rendered templates
+    /// - `source`: Source files for templates, styles, and public files
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use unreact::prelude::*;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let source = Source::memory().file("assets/templates/page.hbs", "Hello {{name}}");
+    ///
+    ///     let mut app = Unreact::with_source(Config::default(), "https://example.com", source)?;
+    ///     app.index("page", object! { name: "World" })?;
+    ///
+    ///     let output = app.build_to_memory()?;
+    ///     assert_eq!(output["index.html"], b"Hello World");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_source(config: Config, url: &str, source: Source) -> Result<Self, Error> {
+        Self::init(config, false, url, source)
+    }
+
+    /// Create a new empty `Unreact` app, with source files
+    fn init(mut config: Config, is_dev: bool, url: &str, source: Source) -> Result<Self, Error> {
         // Use dev build directory if dev mode is active
         if is_dev {
             config.build = DEV_BUILD_DIR.to_string();
         }
 
         // Check that source folders exist and can be accessed
-        check_source_folders(&config)?;
+        source.check_folders(&config)?;
+
+        // Check that config values are valid
+        config.validate()?;
+
+        Self::create(config, is_dev, url, source)
+    }
+
+    /// Create app, without checking source folders or config
+    fn create(config: Config, is_dev: bool, url: &str, source: Source) -> Result<Self, Error> {
+        // Parse ignore patterns
+        let filter = FileFilter::new(&config)?;
 
         // Override url if in dev mode
-        let url = get_url(url, is_dev, config.port);
+        let url = get_url(url, is_dev, &config);
 
         // Create handlebars registry, and register inbuilt partials and helpers
         let mut registry = Handlebars::new();
-        register_inbuilt(&mut registry, &url)?;
+        register_inbuilt(&mut registry, &url, &config)?;
         registry.set_dev_mode(is_dev);
 
         Ok(Self {
             config,
             routes: RouteMap::new(),
             globals: Object::new(),
+            scoped_globals: BTreeMap::new(),
             is_dev,
             handlebars: registry,
             url,
+            filter,
+            route_builder: None,
+            built_routes: HashMap::new(),
+            building: false,
+            render_registry: None,
+            source,
+            command: if is_dev {
+                Command::Watch
+            } else {
+                Command::Build
+            },
         })
     }
 
+    /// Create a new empty `Unreact` app, using command-line arguments
+    ///
+    /// Arguments are merged into the config (see [`Config::apply_args`]), and the command is ran with `.run()`.
+    /// *Dev mode* is active with the `serve` and `watch` commands.
+    /// If `--help` is given, the command is [`Command::Help`], so `.run()` prints the help text without compiling.
+    /// Source folders and config are not checked with `--help`, and routes are not checked for templates.
+    /// Use `.command()` to check the command before creating routes
+    ///
+    /// ```txt
+    /// Usage: app [COMMAND] [OPTIONS]
+    ///
+    /// Commands: build (default), serve, watch, clean
+    /// Options: --port, --build-dir, --no-minify, --url, --drafts, --verbose, --dev, --help
+    /// ```
+    ///
+    /// ## Parameters
+    ///
+    /// - `config`: Configuration for the app (See [`Config`])
+    /// - `url`: The url that should be given to rendered templates, unless `--url` is given. Overridden in *dev mode*
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use unreact::prelude::*;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     // Run with `cargo run -- serve --port 8080`
+    ///     let mut app = Unreact::from_args(Config::default(), "https://example.com")?;
+    ///     app.index("page", object! {})?
+    ///         // Only built with `--drafts`
+    ///         .draft("unfinished", "page", object! {})?;
+    ///     app.run()
+    /// }
+    /// ```
+    pub fn from_args(mut config: Config, url: &str) -> Result<Self, Error> {
+        let args = Args::from_env()?;
+
+        // Print help for any config, even if source folders are missing
+        if args.help {
+            let mut app = Self::create(Config::default(), false, url, Source::memory())?;
+            app.command = Command::Help;
+            return Ok(app);
+        }
+
+        config.apply_args(&args);
+        let url = args.url.as_deref().unwrap_or(url);
+
+        let mut app = Self::new(config, args.is_dev(), url)?;
+        app.command = args.command;
+        Ok(app)
+    }
+
+    /// Get command to run with `.run()`
+    ///
+    /// Set from command-line arguments by [`from_args`](struct.Unreact.html#method.from_args), otherwise `Build`, or `Watch` in *dev mode*
+    pub fn command(&self) -> Command {
+        self.command
+    }
+
     // pub fn registry(&mut self) -> &mut Handlebars {
     //     self.registry
     // }
 
-    /// Set global variables for templates
+    /// Set a callback to create routes, which is ran before every compile
+    ///
+    /// In *dev mode* with the `"watch"` feature, the callback is ran again when files change,
+    /// so route data can be read from disk without restarting.
+    /// Use `Config::watch_paths` to watch data files outside of the source folders
+    ///
+    /// Routes created by the previous run of the callback are removed before it is ran again.
+    /// Routes created outside of the callback are kept
     ///
     /// # Example
     ///
     /// ```rust,no_run
     /// # use unreact::prelude::*;
     /// # fn main() -> Result<(), Error> {
-    /// Unreact::new(Config::default(), false, "https://example.com")?
-    ///     // Index page
+    /// let config = Config {
+    ///     watch_paths: vec!["data".to_string()],
+    ///     ..Config::default()
+    /// };
+    ///
+    /// Unreact::new(config, is_dev(), "https://example.com")?
     ///     .index("page", object! {})?
-    ///     // Globalize does not need to be ran before routes
-    ///     .globalize(object! {smiley: "(^_^)"})
-    ///     // Compiles with a smiley face replacing `{{GLOBAL.smiley}}`
+    ///     .route_builder(|app| {
+    ///         // Read data from disk on every compile
+    ///         let message = std::fs::read_to_string("data/message.txt").unwrap_or_default();
+    ///         app.route("message", "page", object! { message })?;
+    ///         Ok(())
+    ///     })
     ///     .run()
     /// # }
     /// ```
-    pub fn globalize(&mut self, data: Object) -> &mut Self {
-        self.globals = data;
+    pub fn route_builder<F>(&mut self, builder: F) -> &mut Self
+    where
+        F: Fn(&mut Unreact<'a>) -> Result<(), Error> + 'a,
+    {
+        self.route_builder = Some(RouteBuilder(Box::new(builder)));
         self
     }
 
+    /// Run route builder callback (if set), replacing routes from previous run
+    fn build_routes(&mut self) -> Result<(), Error> {
+        let Some(builder) = self.route_builder.take() else {
+            return Ok(());
+        };
+
+        // Remove routes from previous run, restoring any routes they replaced
+        for (path, previous) in std::mem::take(&mut self.built_routes) {
+            match previous {
+                Some(page) => self.routes.insert(path, page),
+                None => self.routes.remove(&path),
+            };
+        }
+
+        self.building = true;
+        let result = (builder.0)(self);
+        self.building = false;
+        self.route_builder = Some(builder);
+        result
+    }
+
+    /// Insert a route, keeping track of routes created by route builder
+    fn insert_route(&mut self, path: String, page: Page) {
+        let previous = self.routes.insert(path.clone(), page);
+        if self.building {
+            // Only keep the route from before the first replacement in this run
+            self.built_routes.entry(path).or_insert(previous);
+        }
+    }
+
     /// Get [`Handlebars`](handlebars) registry as mutable reference
+    ///
+    /// Clears the registry cached by `.render()`
     pub fn handlebars(&mut self) -> &mut Handlebars<'a> {
+        self.render_registry = None;
         &mut self.handlebars
     }
 
@@ -136,13 +322,78 @@ impl<'a> Unreact<'a> {
         &self.url
     }
 
-    /// Compile app to build directory
+    /// Render a single route, and return the page
     ///
-    /// Does not open a dev server, even in *dev mode*
-    fn compile(&self) -> Result<(), Error> {
-        clean_build_dir(&self.config, self.is_dev)?;
+    /// Templates are loaded on the first call, and the registry is cached for later calls (until `.handlebars()` is called).
+    /// If the route does not exist, the route builder is ran first (if set).
+    /// Styles are not compiled, so critical CSS is not inlined, and the full stylesheets are loaded instead
+    ///
+    /// Returns `Err` if the route does not exist, or could not be rendered
+    ///
+    /// - `path`: Path of route, such as `about`, or `/` or `""` for the index route
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use unreact::prelude::*;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let source = Source::memory().file("assets/templates/page.hbs", "Hello {{name}}");
+    ///
+    ///     let mut app = Unreact::with_source(Config::default(), "https://example.com", source)?;
+    ///     app.route("about", "page", object! { name: "World" })?;
+    ///
+    ///     assert_eq!(app.render("/about/")?, "Hello World");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn render(&mut self, path: &str) -> Result<String, Error> {
+        let path = normalize_path(path);
+        let find_route = |routes: &RouteMap| {
+            routes
+                .iter()
+                .find(|(name, _)| normalize_path(name) == path)
+                .map(|(name, page)| (name.clone(), page.clone()))
+        };
+
+        // Run route builder if route does not exist yet
+        let route = match find_route(&self.routes) {
+            Some(route) => Some(route),
+            None => {
+                self.build_routes()?;
+                find_route(&self.routes)
+            }
+        };
+        let Some((name, page)) = route else {
+            return fail!(RouteNotExist, path);
+        };
+
+        let globals = self.globals_for(&name, &page);
+
+        // Create registry with templates, or use cached registry
+        let registry = match &mut self.render_registry {
+            Some(registry) => registry,
+            None => self.render_registry.insert(self.create_registry()?),
+        };
+
+        let (mut content, _) =
+            render_page(registry, &name, &page, globals, &self.config, self.is_dev)?;
 
-        // Create handlebars registry
+        // Remove markers for critical CSS, as styles are not compiled
+        if self.config.critical_css {
+            content = strip_critical_markers(&content)?;
+        }
+
+        base_path_links(
+            content,
+            &name,
+            &self.config.base_path,
+            self.config.rewrite_links,
+        )
+    }
+
+    /// Create [`Handlebars`](handlebars) registry, with custom templates registered, for rendering pages
+    fn create_registry(&self) -> Result<Handlebars<'a>, Error> {
         let mut registry = self.handlebars.clone();
 
         // Enable strict mode if active
@@ -151,67 +402,415 @@ impl<'a> Unreact<'a> {
         }
 
         // Register custom templates
-        let templates = read_folder_recurse(&self.config.templates)?;
+        let templates = self
+            .source
+            .read_folder(&self.config.templates, &self.filter, false)?;
         register_templates(&mut registry, templates)?;
 
-        // Render page and write to files
+        Ok(registry)
+    }
+
+    /// Compile app to build directory, and write report if `report` is set in config
+    ///
+    /// Does not open a dev server, even in *dev mode*
+    fn compile(&self) -> Result<BuildReport, Error> {
+        let start = Instant::now();
+
+        // Compile before writing, so build directory is unchanged if compiling fails
+        let (outputs, copies, mut report) = self.compile_to_memory(true)?;
+
+        // Write to temporary folder, then swap into place, so previous build is kept if writing fails
+        let temp = temp_build_dir(&self.config.build);
+        clean_build_dir(&temp, &self.config)?;
+        if let Err(err) = self.write_outputs(&temp, &outputs, &copies, &mut report) {
+            let _ = remove_build_dir(&temp);
+            return Err(err);
+        }
+
+        swap_build_dir(&temp, &self.config.build)?;
+        report.duration = start.elapsed();
+
+        if !self.config.report.is_empty() {
+            report.write_json(&self.config.report)?;
+        }
+        Ok(report)
+    }
+
+    /// Compile app to a map of output files, by path relative to build directory, and a report of the files
+    ///
+    /// If `copy_public` is `true`, public files on disk are not read, and are returned as a list of files to copy
+    /// (source path, and output path relative to build directory), so large files are not loaded into memory.
+    /// Otherwise public files are included in output files
+    ///
+    /// Does not write any files
+    fn compile_to_memory(
+        &self,
+        copy_public: bool,
+    ) -> Result<(OutputMap, CopyList, BuildReport), Error> {
+        let start = Instant::now();
+        let mut outputs = OutputMap::new();
+        let mut report = BuildReport::default();
+
+        // Create handlebars registry, with custom templates
+        let mut registry = self.create_registry()?;
+
+        // Classes, ids, and tags used by every page, for purging styles
+        let mut used = UsedSelectors::default();
+
+        // Render pages, before writing, so styles can be purged or inlined
+        let mut pages = Vec::new();
         for (name, page) in &self.routes {
+            let render_start = Instant::now();
+
             // Render page with data
-            let content = render_page(
+            let (content, raw_size) = render_page(
                 &mut registry,
+                name,
                 page,
-                self.globals.clone(),
-                self.config.minify,
+                self.globals_for(name, page),
+                &self.config,
                 self.is_dev,
-                self.config.port_ws,
             )?;
 
+            // Add base path to root-relative links
+            let content = base_path_links(
+                content,
+                name,
+                &self.config.base_path,
+                self.config.rewrite_links,
+            )?;
+
+            if self.config.purge {
+                used.scan(&content);
+            }
+            pages.push((name, content));
+
+            report.routes.push(RouteReport {
+                path: name.to_string(),
+                template: match page {
+                    Page::Template { template, .. } => Some(template.to_string()),
+                    Page::Raw(_) => None,
+                },
+                render_time: render_start.elapsed(),
+                raw_size,
+                ..RouteReport::default()
+            });
+        }
+
+        // Compiled css of each stylesheet, before minifying, for inlining critical css
+        let mut compiled = HashMap::new();
+
+        // Convert scss, sass, and css to css
+        let styles = self
+            .source
+            .read_folder(&self.config.styles, &self.filter, true)?;
+        // Source file of each stylesheet name
+        let mut style_files = HashMap::new();
+        for (file, scss) in &styles {
+            // Partials are only used by other styles, and other files are skipped
+            let Some(syntax) = style_syntax(file) else {
+                continue;
+            };
+            if is_partial(file) {
+                continue;
+            }
+            // Name without file extension, keeping parent folders
+            let name = match file.rsplit_once('/') {
+                Some((parent, file)) => format!("{parent}/{}", get_filename(file)),
+                None => get_filename(file).to_string(),
+            };
+            let name = name.as_str();
+
+            // Styles such as `global.scss` and `global.css` would overwrite each other
+            if let Some(other) = style_files.insert(name.to_string(), file) {
+                let (first, second) = if other < file {
+                    (other, file)
+                } else {
+                    (file, other)
+                };
+                return fail!(
+                    DuplicateStyle,
+                    name.to_string(),
+                    first.to_string(),
+                    second.to_string()
+                );
+            }
+
+            // Output file, relative to build directory
+            let path = style_path(&self.config.build_styles, &self.config.style_output, name);
+
+            // Convert to css, then prefix and minify
+            let load_paths = load_paths(&self.config, name);
+            let mut css = scss_to_css(name, scss, syntax, &load_paths, &self.source)?;
+
+            // Remove rules not used by any page
+            if self.config.purge {
+                css = purge_css(&css, &used, &self.config.purge_safelist);
+            }
+
+            if self.config.critical_css {
+                compiled.insert(name.to_string(), css.clone());
+            }
+
+            let mut css = process_css(name, css, self.config.minify, &self.config.targets)?;
+
+            // Checked before source map comment is added
+            if css.trim().is_empty() {
+                report
+                    .warnings
+                    .push(format!("Stylesheet '{name}' is empty"));
+            }
+
+            // Add source map next to output file, always in dev mode
+            if self.config.source_maps || self.is_dev {
+                let output = path.rsplit('/').next().unwrap_or(&path);
+                let build_path = format!("{}/{}", self.config.build, path);
+                let map = source_map(
+                    file,
+                    &css,
+                    &styles,
+                    &self.config.styles,
+                    &build_path,
+                    self.config.approximate_source_maps,
+                );
+                css += &format!("\n/*# sourceMappingURL={output}.map */\n");
+                outputs.insert(format!("{path}.map"), map.into_bytes());
+            }
+            report.styles.push(FileReport {
+                name: name.to_string(),
+                output: path.clone(),
+                size: css.len(),
+            });
+
+            outputs.insert(path, css.into_bytes());
+        }
+
+        if (self.config.source_maps || self.is_dev)
+            && self.config.approximate_source_maps
+            && !report.styles.is_empty()
+        {
+            report
+                .warnings
+                .push("Source maps for styles have approximate mappings".to_string());
+        }
+
+        // Add pages, in the same order as route reports
+        for (index, (name, mut content)) in pages.into_iter().enumerate() {
+            // Inline styles used by page
+            if self.config.critical_css {
+                content = inline_critical_css(
+                    &content,
+                    &compiled,
+                    &self.config.purge_safelist,
+                    self.config.minify,
+                    &self.config.targets,
+                )?;
+            }
+
             // Get filepath
             let path = if name == "404" {
                 // Special case for 404 route
-                format!("{}/404.html", self.config.build)
+                "404.html".to_string()
             } else {
-                // Create folder for `index.html` file
-                let parent = format!("{}/{}", self.config.build, name);
-                try_unwrap!(
-                    fs::create_dir_all(&parent),
-                    else Err(err) => return io_fail!(CreateDir, parent, err),
-                );
                 // Normal path
-                format!("{parent}/index.html")
+                normalize_path(&format!("{name}/index.html"))
             };
 
-            // Write file
-            try_unwrap!(
-                fs::write(&path, content),
-                else Err(err) => return io_fail!(WriteFile, path, err),
-            );
+            if content.trim().is_empty() {
+                report.warnings.push(format!("Route '{name}' is empty"));
+            }
+            // Size of final page, as written
+            report.routes[index].output = path.clone();
+            report.routes[index].size = content.len();
+
+            outputs.insert(path, content.into_bytes());
         }
 
-        // Convert scss to css and write to files
-        let styles = read_folder_recurse(&self.config.styles)?;
-        for (name, scss) in styles {
-            // Create folder for `style.css` file
-            let parent = format!("{}/styles/{}", self.config.build, name);
-            try_unwrap!(
-                fs::create_dir_all(&parent),
-                else Err(err) => return io_fail!(CreateDir, parent, err),
+        // Public directory
+        // Only *copy* directory in production
+        let mut copies = Vec::new();
+        if !self.is_dev && copy_public && matches!(self.source, Source::Disk) {
+            for (path, size) in list_folder_recurse(&self.config.public, &self.filter)? {
+                let output = format!("{}/{path}", self.config.build_public);
+                copies.push((format!("{}/{path}", self.config.public), output.clone()));
+                report.public.push(FileReport {
+                    name: path,
+                    output,
+                    size: size as usize,
+                });
+            }
+        } else if !self.is_dev {
+            let public = self
+                .source
+                .read_folder_bytes(&self.config.public, &self.filter)?;
+            for (path, content) in public {
+                let output = format!("{}/{path}", self.config.build_public);
+                report.public.push(FileReport {
+                    name: path,
+                    output: output.clone(),
+                    size: content.len(),
+                });
+                outputs.insert(output, content);
+            }
+        } else {
+            // Create dummy note file
+            outputs.insert(
+                format!("{}/EMPTY", self.config.build_public),
+                b"'public' folder should always be empty in dev mode".to_vec(),
             );
+        }
 
-            // Convert to scss
-            let css = scss_to_css(&name, &scss, self.config.minify)?;
+        // Check links between pages and files
+        if self.config.check_links != LinkCheck::Off {
+            let copied = copies.iter().map(|(_, output)| output.clone()).collect();
+            let broken = check_links(&outputs, &copied, &self.url, &self.config, self.is_dev);
+            if self.config.check_links == LinkCheck::Error && !broken.is_empty() {
+                return fail!(BrokenLinks, broken);
+            }
+            report.warnings.extend(broken);
+        }
 
-            // Write file
-            let path = format!("{}/style.css", parent);
-            try_unwrap!(
-                fs::write(&path, css),
-                else Err(err) => return io_fail!(WriteFile, path, err),
-            );
+        // Compressed copies of text files, for hosts which serve them
+        // Skipped in dev mode (as compressing is slow), unless the dev server serves them
+        #[cfg(feature = "compress")]
+        if self.config.compress && (!self.is_dev || self.config.serve_compressed) {
+            compress_outputs(&mut outputs, &self.config)?;
+        }
+
+        if !self.routes.contains_key("") {
+            report.warnings.push("No index route".to_string());
+        }
+
+        // Sort, as routes and files are read in any order
+        report.routes.sort_by(|a, b| a.path.cmp(&b.path));
+        report.styles.sort_by(|a, b| a.name.cmp(&b.name));
+        report.public.sort_by(|a, b| a.name.cmp(&b.name));
+
+        report.files = outputs.len() + copies.len();
+        report.total_size = outputs.values().map(Vec::len).sum();
+        if !copies.is_empty() {
+            report.total_size += report.public.iter().map(|file| file.size).sum::<usize>();
         }
+        report.duration = start.elapsed();
 
+        Ok((outputs, copies, report))
+    }
+
+    /// Write output files, and copy public files, to a folder (such as temporary build folder)
+    ///
+    /// Compressed copies of public files are also written, with the `"compress"` feature
+    fn write_outputs(
+        &self,
+        folder: &str,
+        outputs: &OutputMap,
+        copies: &CopyList,
+        #[allow(unused_variables)] report: &mut BuildReport,
+    ) -> Result<(), Error> {
+        for (path, content) in outputs {
+            self.write_output(folder, path, content)?;
+        }
+
+        for (from, path) in copies {
+            copy_file(from, &format!("{folder}/{path}"))?;
+            if self.config.verbose {
+                println!("Copied {}/{path}", self.config.build);
+            }
+
+            // Only text files are compressed, so can be read into memory
+            #[cfg(feature = "compress")]
+            if self.config.compress {
+                let size = std::fs::metadata(from).map_or(0, |metadata| metadata.len());
+                if is_compressible(path, size as usize, &self.config) {
+                    let content = try_unwrap!(
+                        std::fs::read(from),
+                        else Err(err) => return io_fail!(ReadFile, from.to_string(), err),
+                    );
+                    for (path, content) in compress_file(path, &content, &self.config)? {
+                        self.write_output(folder, &path, &content)?;
+                        report.files += 1;
+                        report.total_size += content.len();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a file to a folder (such as temporary build folder), and print path in build directory if verbose
+    fn write_output(&self, folder: &str, path: &str, content: &[u8]) -> Result<(), Error> {
+        write_file(&format!("{folder}/{path}"), content)?;
+        if self.config.verbose {
+            println!("Wrote {}/{path}", self.config.build);
+        }
         Ok(())
     }
 
+    /// Remove build directories, including *dev mode* build directory
+    fn clean(&self) -> Result<(), Error> {
+        for folder in [&self.config.build, DEV_BUILD_DIR] {
+            // Leftover temporary folder, if writing was interrupted
+            remove_build_dir(&temp_build_dir(folder))?;
+            remove_build_dir(folder)?;
+            if self.config.verbose {
+                println!("Removed {folder}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile app to a map of output files, without writing to the build directory
+    ///
+    /// Returns a map of output paths (relative to build directory, such as `index.html` or `styles/global/style.css`) and file contents.
+    /// Routes are built first, as with `.run()`
+    ///
+    /// Useful for testing, or for serving or uploading the built site without writing files.
+    /// Use with [`Unreact::with_source`] to also read source files from memory
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use unreact::prelude::*;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let mut app = Unreact::new(Config::default(), false, "https://example.com")?;
+    ///     app.index("page", object! { foo: "World!" })?;
+    ///
+    ///     let output = app.build_to_memory()?;
+    ///     let index = String::from_utf8_lossy(&output["index.html"]);
+    ///     assert!(index.contains("World!"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn build_to_memory(&mut self) -> Result<OutputMap, Error> {
+        self.build_routes()?;
+        let (outputs, _, _) = self.compile_to_memory(false)?;
+        Ok(outputs)
+    }
+
+    /// Compile app to build directory, and return a report of the files written
+    ///
+    /// Same as `.run()` with the `build` command, but does not open a dev server, even in *dev mode*.
+    /// The report is also written as JSON, if `report` is set in config
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use unreact::prelude::*;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let mut app = Unreact::new(Config::default(), false, "https://example.com")?;
+    ///     app.index("page", object! { foo: "World!" })?;
+    ///
+    ///     let report = app.build()?;
+    ///     println!("{report}");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn build(&mut self) -> Result<BuildReport, Error> {
+        self.build_routes()?;
+        self.compile()
+    }
+
     /// Compile app to build directory
     ///
     /// Compile app to build directory
@@ -240,8 +839,18 @@ impl<'a> Unreact<'a> {
     /// }
     /// ```
     #[cfg(not(feature = "dev"))]
-    pub fn run(&self) -> Result<(), Error> {
-        self.compile()
+    pub fn run(&mut self) -> Result<(), Error> {
+        match self.command {
+            Command::Clean => return self.clean(),
+            Command::Help => {
+                println!("{}", Args::help_text());
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        self.build()?;
+        Ok(())
     }
 
     /// Compile app to build directly, and open local server if *dev mode* is active
@@ -268,17 +877,27 @@ impl<'a> Unreact<'a> {
     /// }
     /// ```
     #[cfg(feature = "dev")]
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&mut self) -> Result<(), Error> {
         use crate::server;
         use stilo::{eprintln_styles, print_styles, println_styles};
 
+        match self.command {
+            Command::Clean => return self.clean(),
+            Command::Help => {
+                println!("{}", Args::help_text());
+                return Ok(());
+            }
+            _ => (),
+        }
+
         // Just compile if not dev mode
         if !self.is_dev {
-            return self.compile();
+            self.build()?;
+            return Ok(());
         }
 
         // Create callback with non-breaking error message
-        let run_compile = || {
+        let run_compile = |app: &mut Self| {
             // Clear terminal
             print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
 
@@ -295,14 +914,13 @@ impl<'a> Unreact<'a> {
             }
             println_styles!(
                 "\n    Listening on ": Green + bold;
-                "http://localhost:{}": Green + bold + underline, self.config.port;
+                "{}": Green + bold + underline, app.url;
             );
-            #[cfg(feature = "watch")]
-            {
+            if cfg!(feature = "watch") && app.config.watch {
                 println_styles!("    Watching files for changes...": Cyan);
-            }
-            #[cfg(not(feature = "watch"))]
-            {
+            } else if cfg!(feature = "watch") {
+                println_styles!("    Note: ": Yellow + bold; "Not watching files": Yellow;);
+            } else {
                 println_styles!(
                     "    Note: ": Yellow + bold;
                     "\"watch\"": Yellow + italic;
@@ -311,10 +929,13 @@ impl<'a> Unreact<'a> {
             }
             println!();
 
-            // Compile it now
-            match self.compile() {
+            // Create routes and compile it now
+            match app.build() {
                 // Success
-                Ok(()) => println_styles!("Compiled successfully!": Green + bold),
+                Ok(report) => {
+                    println!("{report}\n");
+                    println_styles!("Compiled successfully!": Green + bold);
+                }
                 // Error
                 Err(err) => eprintln_styles!(
                     "Error compiling in dev mode:": Red + bold;
@@ -324,56 +945,125 @@ impl<'a> Unreact<'a> {
         };
 
         // Compile for first time
-        run_compile();
+        run_compile(self);
 
-        // For "watch" feature
+        // For "watch" feature, if watching files
         #[cfg(feature = "watch")]
-        {
+        if self.config.watch {
             // Open server in new thread
-            let port = self.config.port;
-            let port_ws = self.config.port_ws;
-            let public = self.config.public.clone();
-            std::thread::spawn(move || server::listen(port, &public, port_ws));
-
-            // Folders to watch
-            let watched_folders = &[
-                self.config.templates.as_str(),
-                self.config.styles.as_str(),
-                self.config.public.as_str(),
+            let config = self.config.clone();
+            std::thread::spawn(move || server::listen(&config));
+
+            // Folders to watch, including extra paths
+            let mut watched_folders = vec![
+                (server::Folder::Templates, self.config.templates.clone()),
+                (server::Folder::Styles, self.config.styles.clone()),
+                (server::Folder::Public, self.config.public.clone()),
             ];
+            for path in &self.config.watch_paths {
+                watched_folders.push((server::Folder::Other, path.clone()));
+            }
+
+            let filter = self.filter.clone();
+            let port_ws = self.config.port_ws;
+            let debounce = std::time::Duration::from_millis(self.config.watch_debounce);
+
+            // Recreate routes and recompile, and find which routes were rebuilt
+            let router = |changes: &[server::Change]| {
+                run_compile(self);
+                self.rebuilt_routes(changes)
+            };
 
             // Watch files for changes
-            server::watch(run_compile, watched_folders, self.config.port_ws);
+            server::watch(router, &watched_folders, &filter, port_ws, debounce);
+            return Ok(());
         }
 
-        // For NOT "watch" feature
-        #[cfg(not(feature = "watch"))]
+        // Open server in current thread, without watching files
+        server::listen(&self.config);
+
+        Ok(())
+    }
+
+    /// Get the routes affected by changed files, using template dependencies
+    ///
+    /// Any route may be affected, unless only templates were changed
+    #[cfg(feature = "watch")]
+    fn rebuilt_routes(&self, changes: &[crate::server::Change]) -> crate::server::Rebuilt {
+        use crate::{
+            convert::routes_using_templates,
+            server::{Folder, Rebuilt},
+        };
+
+        if changes.is_empty()
+            || changes
+                .iter()
+                .any(|change| change.folder != Folder::Templates)
         {
-            // Open server in current thread
-            server::listen(self.config.port, self.config.public, self.config.port_ws);
+            return Rebuilt::All;
         }
 
-        Ok(())
+        let Ok(templates) = self
+            .source
+            .read_folder(&self.config.templates, &self.filter, false)
+        else {
+            return Rebuilt::All;
+        };
+        let changed: Vec<_> = changes.iter().map(|change| change.name.clone()).collect();
+
+        match routes_using_templates(&self.routes, &templates, &changed) {
+            // 404 page is shown on any unknown path
+            Some(routes) if !routes.iter().any(|route| route == "404") => Rebuilt::Routes(routes),
+            _ => Rebuilt::All,
+        }
     }
 }
 
 /// Get the url for the site
 ///
-/// Returns url given, unless `"dev"` feature is enabled and *dev mode* is active
+/// Returns url given, unless `"dev"` feature is enabled and *dev mode* is active.
+/// In *dev mode*, url includes base path of config
 fn get_url(
     url: &str,
     // Only for "dev" feature
     #[allow(unused_variables)] is_dev: bool,
-    #[allow(unused_variables)] port: Port,
+    #[allow(unused_variables)] config: &Config,
 ) -> String {
     // If `watch` feature is used, and `is_dev`
     #[cfg(feature = "dev")]
     {
         if is_dev {
-            return format!("http://localhost:{}/", port);
+            if config.base_path.is_empty() {
+                return format!("http://localhost:{}/", config.port);
+            }
+            return format!("http://localhost:{}/{}/", config.port, config.base_path);
         }
     }
 
     // Default (add slash to end if not included)
     url.to_string() + if url.ends_with('/') { "" } else { "/" }
 }
+
+/// Convert serializable data to an `Object`, for route data and globals
+///
+/// - `name`: Name of data, for error message
+///
+/// Returns `Err` if data could not be serialized, or is not an object
+fn to_object(data: impl Serialize, name: &str) -> Result<Object, Error> {
+    let kind = match try_unwrap!(
+        serde_json::to_value(data),
+        else Err(err) => return fail!(InvalidData, name.to_string(), err.to_string()),
+    ) {
+        Value::Object(object) => return Ok(object),
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+    };
+    fail!(
+        InvalidData,
+        name.to_string(),
+        format!("expected an object, found {kind}")
+    )
+}
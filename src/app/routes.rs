@@ -1,6 +1,7 @@
-use std::path::Path;
+use serde::Serialize;
 
-use crate::{Error, Object, Page, Unreact};
+use super::to_object;
+use crate::{Command, Error, Page, Unreact};
 
 /// Append shared documentation attributes to each function
 macro_rules! include_shared_docs {
@@ -41,6 +42,7 @@ macro_rules! include_shared_docs {
             /// - [`route_raw_html`](struct.Unreact.html#method.route_raw_html): Create a HTML page route without a template
             /// - [`index`](struct.Unreact.html#method.index): Create an index route (`/`)
             /// - [`not_found`](struct.Unreact.html#method.not_found): Create a 404 route (`/404`)
+            /// - [`draft`](struct.Unreact.html#method.draft): Create a normal route, only if drafts are enabled
             $item
         )*
     };
@@ -52,23 +54,26 @@ impl<'a> Unreact<'a> {
         ///
         /// **NOTE**: Route will only validate if template exists in production. In dev mode, this function **will always pass**, and error will occur during `run` function
         ///
+        /// Returns `Err` if data could not be serialized, or is not an object (in dev mode also)
+        ///
         /// ## Parameters
         ///
         /// - `path`: The folder (relative to build directory) that file should be written in (`{build}/{path}/index.html`)
         /// - `template`: The name of the template to use
-        /// - `data`: Data to pass into the template, as an `Object`
+        /// - `data`: Data to pass into the template, as an `Object`, or any value which serializes to an object
         <::>
-        pub fn route(&mut self, path: &str, template: &str, data: Object) -> Result<&mut Self, Error> {
-            // Check file exists - only if NOT dev mode
-            if !self.is_dev{
+        pub fn route(&mut self, path: &str, template: &str, data: impl Serialize) -> Result<&mut Self, Error> {
+            // Check file exists - only if NOT dev mode, and not printing help
+            if !self.is_dev && self.command != Command::Help {
                 let file_path = format!("{}/{}.hbs", self.config.templates, template);
-                if !Path::new(&file_path).exists() {
+                if !self.source.is_file(&file_path) {
                     return fail!(TemplateNotExist, template.to_string());
                 }
             }
 
             // Create route
-            self.routes.insert(
+            let data = to_object(data, &format!("route '{path}'"))?;
+            self.insert_route(
                 path.to_string(),
                 Page::Template {
                     template: template.to_string(),
@@ -87,7 +92,7 @@ impl<'a> Unreact<'a> {
         /// - `content`: The raw file contents to write to the file
         <::>
         pub fn route_raw(&mut self, path: &str, content: impl Into<String>) -> &mut Self {
-            self.routes.insert(path.to_string(), Page::Raw(content.into()));
+            self.insert_route(path.to_string(), Page::Raw(content.into()));
             self
         }
 
@@ -101,7 +106,7 @@ impl<'a> Unreact<'a> {
         /// - `content`: The raw file contents to write to the file
         <::>
         pub fn route_raw_html(&mut self, path: &str, content: impl Into<String>) -> &mut Self {
-            self.routes.insert(path.to_string(), Page::Raw(format!(include_str!("boilerplate.html"), CONTENT = content.into())));
+            self.insert_route(path.to_string(), Page::Raw(format!(include_str!("boilerplate.html"), CONTENT = content.into())));
             self
         }
 
@@ -116,9 +121,9 @@ impl<'a> Unreact<'a> {
         /// ## Parameters
         ///
         /// - `template`: The name of the template to use
-        /// - `data`: Data to pass into the template, as an `Object`
+        /// - `data`: Data to pass into the template, as an `Object`, or any value which serializes to an object
         <::>
-        pub fn index(&mut self, template: &str, data: Object) -> Result<&mut Self, Error> {
+        pub fn index(&mut self, template: &str, data: impl Serialize) -> Result<&mut Self, Error> {
             self.route("", template, data)
         }
 
@@ -134,10 +139,30 @@ impl<'a> Unreact<'a> {
         /// ## Parameters
         ///
         /// - `template`: The name of the template to use
-        /// - `data`: Data to pass into the template, as an `Object`
+        /// - `data`: Data to pass into the template, as an `Object`, or any value which serializes to an object
         <::>
-        pub fn not_found(&mut self, template: &str, data: Object) -> Result<&mut Self, Error> {
+        pub fn not_found(&mut self, template: &str, data: impl Serialize) -> Result<&mut Self, Error> {
             self.route("404", template, data)
         }
+
+        /// Create a draft route
+        ///
+        /// Same as `app.route(...)`, but the route is only created if `drafts` is enabled in config (or with `--drafts` argument).
+        /// Otherwise the route is skipped
+        ///
+        /// **NOTE**: Route will only validate if template exists in production, and if drafts are enabled. In dev mode, this function **will always pass**, and error will occur during `run` function
+        ///
+        /// ## Parameters
+        ///
+        /// - `path`: The folder (relative to build directory) that file should be written in (`{build}/{path}/index.html`)
+        /// - `template`: The name of the template to use
+        /// - `data`: Data to pass into the template, as an `Object`, or any value which serializes to an object
+        <::>
+        pub fn draft(&mut self, path: &str, template: &str, data: impl Serialize) -> Result<&mut Self, Error> {
+            if !self.config.drafts {
+                return Ok(self);
+            }
+            self.route(path, template, data)
+        }
     );
 }
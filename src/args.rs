@@ -0,0 +1,283 @@
+use crate::{Config, Error, Port};
+
+/// Help text for command-line arguments, with `{program}` as the program name
+const HELP: &str = "\
+Usage: {program} [COMMAND] [OPTIONS]
+
+Commands:
+  build    Compile site to build directory (default)
+  serve    Compile in dev mode, and open dev server
+  watch    Compile in dev mode, open dev server, and recompile when files change
+  clean    Remove build directories
+
+Options:
+  -p, --port <PORT>       Port for dev server (websocket port is moved if it clashes)
+  -b, --build-dir <DIR>   Output folder for built files (not used in dev mode)
+      --no-minify         Do not minify output files
+  -u, --url <URL>         Url of site given to templates (overridden in dev mode)
+      --drafts            Build draft routes
+  -v, --verbose           Print every file written
+  -d, --dev               Alias of `watch` command
+  -h, --help              Print help
+";
+
+/// Command to run, from command-line arguments
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Command {
+    /// Compile app to build directory
+    #[default]
+    Build,
+    /// Compile app in *dev mode*, and open dev server
+    ///
+    /// Only opens a dev server with the `"dev"` or `"watch"` features enabled
+    Serve,
+    /// Compile app in *dev mode*, open dev server, and recompile when files change
+    ///
+    /// Only watches files with the `"watch"` feature enabled
+    Watch,
+    /// Remove build directories (including *dev mode* build directory)
+    Clean,
+    /// Print help text, without compiling
+    ///
+    /// Used by [`Unreact::from_args`](struct.Unreact.html#method.from_args) when `--help` is given
+    Help,
+}
+
+/// Parsed command-line arguments
+///
+/// Use [`Unreact::from_args`](struct.Unreact.html#method.from_args) to create an app from arguments,
+/// or [`Config::apply_args`] to merge arguments into config
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// Command to run
+    pub command: Command,
+    /// `--port`: Port for dev server
+    pub port: Option<Port>,
+    /// `--build-dir`: Output folder for built files
+    pub build_dir: Option<String>,
+    /// `--no-minify`: Do not minify output files
+    pub no_minify: bool,
+    /// `--url`: Url of site given to templates
+    pub url: Option<String>,
+    /// `--drafts`: Build draft routes
+    pub drafts: bool,
+    /// `--verbose`: Print every file written
+    pub verbose: bool,
+    /// `--help`: Print help
+    pub help: bool,
+}
+
+impl Args {
+    /// Parse arguments of current process (`std::env::args`), skipping program name
+    pub fn from_env() -> Result<Self, Error> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Parse arguments, not including program name
+    ///
+    /// Options can be given as `--port 3000` or `--port=3000`
+    pub fn parse<I, S>(args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = Self::default();
+        let mut command = None;
+
+        let mut args = args.into_iter().map(Into::into);
+        while let Some(arg) = args.next() {
+            // Split `--option=value`
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            // Value of option, inline or next argument
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| Error::InvalidArgs(format!("missing value for `{name}`")))
+            };
+
+            match name {
+                "-p" | "--port" => {
+                    let port = value()?;
+                    parsed.port = Some(
+                        port.parse()
+                            .map_err(|_| Error::InvalidArgs(format!("invalid port `{port}`")))?,
+                    );
+                }
+                "-b" | "--build-dir" => parsed.build_dir = Some(value()?),
+                "-u" | "--url" => parsed.url = Some(value()?),
+                "--no-minify" => parsed.no_minify = true,
+                "--drafts" => parsed.drafts = true,
+                "-v" | "--verbose" => parsed.verbose = true,
+                "-h" | "--help" => parsed.help = true,
+                "-d" | "--dev" => set_command(&mut command, Command::Watch, &arg)?,
+
+                _ if arg.starts_with('-') => {
+                    return fail!(InvalidArgs, format!("unknown option `{arg}`"));
+                }
+
+                "build" => set_command(&mut command, Command::Build, &arg)?,
+                "serve" => set_command(&mut command, Command::Serve, &arg)?,
+                "watch" => set_command(&mut command, Command::Watch, &arg)?,
+                "clean" => set_command(&mut command, Command::Clean, &arg)?,
+                _ => return fail!(InvalidArgs, format!("unknown command `{arg}`")),
+            }
+        }
+
+        parsed.command = command.unwrap_or_default();
+        Ok(parsed)
+    }
+
+    /// Whether the command runs in *dev mode*
+    pub fn is_dev(&self) -> bool {
+        matches!(self.command, Command::Serve | Command::Watch)
+    }
+
+    /// Get help text, with the file name of the current program
+    pub fn help_text() -> String {
+        let program = std::env::args()
+            .next()
+            .and_then(|path| {
+                let name = std::path::Path::new(&path)
+                    .file_stem()?
+                    .to_str()?
+                    .to_string();
+                Some(name)
+            })
+            .unwrap_or_else(|| "app".to_string());
+        HELP.replace("{program}", &program)
+    }
+}
+
+/// Set command, if not already set to a different command
+fn set_command(command: &mut Option<Command>, new: Command, arg: &str) -> Result<(), Error> {
+    match command {
+        Some(command) if *command != new => {
+            fail!(
+                InvalidArgs,
+                format!("unexpected command `{arg}`, only one command can be given")
+            )
+        }
+        _ => {
+            *command = Some(new);
+            Ok(())
+        }
+    }
+}
+
+impl Config {
+    /// Merge command-line arguments into config
+    ///
+    /// `--port`, `--build-dir`, `--no-minify`, `--drafts`, and `--verbose` override config values.
+    /// If `--port` is the same as `port_ws`, `port_ws` is moved to the next port (or previous, for the highest port).
+    /// The `serve` command disables watching files
+    pub fn apply_args(&mut self, args: &Args) {
+        if let Some(port) = args.port {
+            self.port = port;
+            if self.port_ws == port {
+                self.port_ws = port.checked_add(1).unwrap_or(port - 1);
+            }
+        }
+        if let Some(build) = &args.build_dir {
+            self.build = build.clone();
+        }
+        if args.no_minify {
+            self.minify = false;
+        }
+        if args.drafts {
+            self.drafts = true;
+        }
+        if args.verbose {
+            self.verbose = true;
+        }
+        if args.command == Command::Serve {
+            self.watch = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        assert_eq!(Args::parse(Vec::<String>::new()).unwrap(), Args::default());
+
+        let args = Args::parse([
+            "serve",
+            "--port=8080",
+            "-b",
+            "dist",
+            "--no-minify",
+            "--url",
+            "https://example.com",
+            "--drafts",
+            "-v",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                command: Command::Serve,
+                port: Some(8080),
+                build_dir: Some("dist".to_string()),
+                no_minify: true,
+                url: Some("https://example.com".to_string()),
+                drafts: true,
+                verbose: true,
+                help: false,
+            }
+        );
+        assert!(args.is_dev());
+
+        assert_eq!(Args::parse(["--dev"]).unwrap().command, Command::Watch);
+        assert_eq!(Args::parse(["clean"]).unwrap().command, Command::Clean);
+        assert!(Args::parse(["-h"]).unwrap().help);
+
+        for invalid in [
+            &["--port"][..],
+            &["--port", "http"],
+            &["--unknown"],
+            &["deploy"],
+            &["build", "watch"],
+        ] {
+            assert!(
+                matches!(
+                    Args::parse(invalid.iter().copied()),
+                    Err(Error::InvalidArgs(_))
+                ),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn apply_args_works() {
+        let mut config = Config::default();
+        config.apply_args(&Args::parse(["serve", "-p", "4000", "--no-minify"]).unwrap());
+
+        assert_eq!(config.port, 4000);
+        assert!(!config.minify);
+        assert!(!config.watch);
+        assert_eq!(config.build, Config::default().build);
+        assert!(!config.drafts);
+
+        let mut config = Config::default();
+        config.apply_args(&Args::parse(["--port", "3001"]).unwrap());
+        assert_eq!(config.port, 3001);
+        assert_eq!(config.port_ws, 3002);
+        assert!(config.validate().is_ok());
+
+        let mut config = Config {
+            port_ws: u16::MAX,
+            ..Config::default()
+        };
+        config.apply_args(&Args::parse(["--port", "65535"]).unwrap());
+        assert_eq!(config.port_ws, u16::MAX - 1);
+    }
+}
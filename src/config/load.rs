@@ -0,0 +1,455 @@
+use std::{fs, path::Path};
+
+use toml::Value as TomlValue;
+
+use crate::{convert::parse_targets, files::normalize_path, Config, Error};
+
+/// Config file in working directory, read by `Config::load`
+const CONFIG_FILE: &str = "unreact.toml";
+/// Cargo manifest in working directory, with config in `[package.metadata.unreact]` table
+const CARGO_FILE: &str = "Cargo.toml";
+/// Prefix of environment variables which override config values
+const ENV_PREFIX: &str = "UNREACT_";
+
+impl Config {
+    /// Load config from the working directory, with environment variable overrides, and validate
+    ///
+    /// 1. Reads `unreact.toml` if it exists, otherwise the `[package.metadata.unreact]` table of `Cargo.toml` (if it exists), otherwise uses default values
+    /// 2. Overrides values with `UNREACT_*` environment variables (see [`apply_env`](struct.Config.html#method.apply_env))
+    /// 3. Validates values (see [`validate`](struct.Config.html#method.validate))
+    ///
+    /// # Example
+    ///
+    /// `unreact.toml`:
+    ///
+    /// ```toml
+    /// build = "dist"
+    /// minify = false
+    /// ignore = ["*.draft.hbs"]
+    /// ```
+    ///
+    /// ```rust,no_run
+    /// # use unreact::prelude::*;
+    /// # fn main() -> Result<(), Error> {
+    /// // Run with `UNREACT_PORT=8080` to override port
+    /// let config = Config::load()?;
+    /// Unreact::new(config, is_dev(), "https://example.com")?
+    ///     .index("page", object! {})?
+    ///     .run()
+    /// # }
+    /// ```
+    pub fn load() -> Result<Self, Error> {
+        let mut config = if Path::new(CONFIG_FILE).is_file() {
+            Self::read_file(CONFIG_FILE)?
+        } else if Path::new(CARGO_FILE).is_file() {
+            Self::read_file(CARGO_FILE)?
+        } else {
+            Self::default()
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read config from a toml file, and validate
+    ///
+    /// If the file is named `Cargo.toml`, config is read from the `[package.metadata.unreact]` table, or default values are used if the table does not exist.
+    /// Otherwise the whole file is read as config.
+    ///
+    /// Missing values use the default value. Unknown keys are an error
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let config = Self::read_file(path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read config from a toml file, without validating
+    fn read_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let display = path.display().to_string();
+
+        let file = try_unwrap!(
+            fs::read_to_string(path),
+            else Err(err) => return io_fail!(ReadFile, display, err),
+        );
+
+        let is_cargo = path.file_name().is_some_and(|name| name == CARGO_FILE);
+        Self::parse(&file, is_cargo).map_err(|err| Error::ParseConfig(display, Box::new(err)))
+    }
+
+    /// Parse config from toml, optionally from the `[package.metadata.unreact]` table of a Cargo manifest
+    fn parse(file: &str, is_cargo: bool) -> Result<Self, toml::de::Error> {
+        if !is_cargo {
+            return toml::from_str(file);
+        }
+
+        let manifest: TomlValue = toml::from_str(file)?;
+        match manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("unreact"))
+        {
+            Some(table) => table.clone().try_into(),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Override config values with `UNREACT_*` environment variables
+    ///
+    /// The rest of the variable name is the config key, such as `UNREACT_BUILD` for `build`, or `UNREACT_PORT_WS` for `port_ws`.
+    /// Lists (such as `ignore`) are separated with commas, and booleans can be `true`, `false`, `1`, or `0`
+    ///
+    /// Returns `Err` if a variable does not match a config key, or has an invalid value
+    pub fn apply_env(&mut self) -> Result<(), Error> {
+        self.apply_vars(std::env::vars())
+    }
+
+    /// Override config values with variables, such as `UNREACT_BUILD`
+    ///
+    /// Variables without the prefix are skipped
+    fn apply_vars(&mut self, vars: impl Iterator<Item = (String, String)>) -> Result<(), Error> {
+        let vars: Vec<_> = vars
+            .filter(|(var, _)| var.starts_with(ENV_PREFIX))
+            .collect();
+        if vars.is_empty() {
+            return Ok(());
+        }
+
+        // Values which cannot be represented in toml (such as numbers above `i64::MAX`) cannot be overridden
+        let table = try_unwrap!(
+            TomlValue::try_from(&*self),
+            else Err(err) => return fail!(ConfigEnv, ENV_PREFIX.to_string() + "*", err.to_string()),
+        );
+        let TomlValue::Table(mut table) = table else {
+            unreachable!("Config should serialize as a table");
+        };
+
+        for (var, value) in vars {
+            let key = var[ENV_PREFIX.len()..].to_lowercase();
+
+            // Parse value as type of existing value
+            let value = match table.get(&key) {
+                Some(TomlValue::String(_)) => TomlValue::String(value),
+                Some(TomlValue::Boolean(_)) => match value.trim() {
+                    "true" | "1" => TomlValue::Boolean(true),
+                    "false" | "0" => TomlValue::Boolean(false),
+                    _ => return fail!(ConfigEnv, var, "expected `true` or `false`".to_string()),
+                },
+                Some(TomlValue::Integer(_)) => match value.trim().parse() {
+                    Ok(value) => TomlValue::Integer(value),
+                    Err(_) => return fail!(ConfigEnv, var, "expected a number".to_string()),
+                },
+                Some(TomlValue::Array(_)) => TomlValue::Array(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(|item| TomlValue::String(item.to_string()))
+                        .collect(),
+                ),
+                _ => return fail!(ConfigEnv, var, "unknown config key".to_string()),
+            };
+
+            table.insert(key, value);
+        }
+
+        *self = try_unwrap!(
+            TomlValue::Table(table).try_into(),
+            else Err(err) => return fail!(ConfigEnv, ENV_PREFIX.to_string() + "*", err.to_string()),
+        );
+        Ok(())
+    }
+
+    /// Check that config values are valid
+    ///
+    /// - Folders must not be empty
+    /// - Build folder must not be the working directory, or contain a source folder, as it is removed before compiling
+    /// - Ports must not be `0`, and must be different
+    /// - `style_output` must include `{name}`
+    /// - Build subfolders must be relative paths without `./`, `..`, or leading and trailing slashes, and must be different
+    /// - `base_path` must be empty, or a relative path (as with build subfolders), and must not contain `?` or `#`
+    /// - Compression levels must be from `0` to `9` for gzip, and `0` to `11` for brotli
+    pub fn validate(&self) -> Result<(), Error> {
+        let folders = [
+            ("build", &self.build),
+            ("templates", &self.templates),
+            ("styles", &self.styles),
+            ("public", &self.public),
+        ];
+        for (key, folder) in folders {
+            if folder.trim().is_empty() {
+                return fail!(
+                    InvalidConfig,
+                    key.to_string(),
+                    "folder must not be empty".to_string()
+                );
+            }
+        }
+
+        let build = normalize_path(&self.build);
+        if build.is_empty() {
+            return fail!(
+                InvalidConfig,
+                "build".to_string(),
+                "build folder must not be the working directory".to_string()
+            );
+        }
+        for (key, folder) in &folders[1..] {
+            let folder = normalize_path(folder);
+            if folder == build || folder.starts_with(&format!("{build}/")) {
+                return fail!(
+                    InvalidConfig,
+                    "build".to_string(),
+                    format!("build folder must not contain `{key}` source folder, as it is removed before compiling")
+                );
+            }
+        }
+
+        if self.port == 0 || self.port_ws == 0 {
+            return fail!(
+                InvalidConfig,
+                "port".to_string(),
+                "port must not be `0`".to_string()
+            );
+        }
+        if self.port == self.port_ws {
+            return fail!(
+                InvalidConfig,
+                "port_ws".to_string(),
+                format!(
+                    "websocket port must be different to server port `{}`",
+                    self.port
+                )
+            );
+        }
+
+        if !self.style_output.contains("{name}") {
+            return fail!(InvalidStyleOutput, self.style_output.clone());
+        }
+
+        let subfolders = [
+            ("build_styles", &self.build_styles),
+            ("build_public", &self.build_public),
+        ];
+        for (key, folder) in subfolders {
+            if folder.is_empty()
+                || normalize_path(folder) != *folder
+                || folder.split('/').any(|part| part == "..")
+            {
+                return fail!(
+                    InvalidConfig,
+                    key.to_string(),
+                    format!(
+                        "build subfolder `{folder}` must be a relative path, such as `assets/css`"
+                    )
+                );
+            }
+        }
+        let base_path = &self.base_path;
+        if !base_path.is_empty()
+            && (normalize_path(base_path) != *base_path
+                || base_path.split('/').any(|part| part == "..")
+                || base_path.contains(['?', '#']))
+        {
+            return fail!(
+                InvalidConfig,
+                "base_path".to_string(),
+                format!("base path `{base_path}` must be a path without leading or trailing slashes, such as `project`")
+            );
+        }
+
+        if self.gzip_level > 9 {
+            return fail!(
+                InvalidConfig,
+                "gzip_level".to_string(),
+                "gzip level must be from `0` to `9`".to_string()
+            );
+        }
+        if self.brotli_level > 11 {
+            return fail!(
+                InvalidConfig,
+                "brotli_level".to_string(),
+                "brotli level must be from `0` to `11`".to_string()
+            );
+        }
+
+        // Checked without the "prefix" feature too, so invalid targets are not silently ignored
+        parse_targets(&self.targets)?;
+
+        // Neither subfolder can be inside the other, as public files could overwrite styles
+        let (styles, public) = (&self.build_styles, &self.build_public);
+        if styles == public
+            || styles.starts_with(&format!("{public}/"))
+            || public.starts_with(&format!("{styles}/"))
+        {
+            return fail!(
+                InvalidConfig,
+                "build_public".to_string(),
+                "build subfolders for styles and public files must be different, and not inside each other".to_string()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn parse_works() {
+        let config = Config::parse("build = \"dist\"\nminify = false\nport = 8080", false).unwrap();
+        assert_eq!(config.build, "dist");
+        assert!(!config.minify);
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.templates, Config::default().templates);
+
+        // Unknown key
+        assert!(Config::parse("buidl = \"dist\"", false).is_err());
+        // Wrong type
+        assert!(Config::parse("minify = \"no\"", false).is_err());
+
+        let cargo = "\
+[package]
+name = \"app\"
+
+[package.metadata.unreact]
+styles = \"css\"
+purge_safelist = [\"js-*\"]
+";
+        let config = Config::parse(cargo, true).unwrap();
+        assert_eq!(config.styles, "css");
+        assert_eq!(config.purge_safelist, ["js-*"]);
+
+        let config = Config::parse("[package]\nname = \"app\"", true).unwrap();
+        assert_eq!(config.styles, Config::default().styles);
+    }
+
+    #[test]
+    fn apply_vars_works() {
+        let mut config = Config::default();
+        config
+            .apply_vars(vars(&[
+                ("UNREACT_BUILD", "dist"),
+                ("UNREACT_MINIFY", "0"),
+                ("UNREACT_PORT_WS", "4001"),
+                ("UNREACT_WATCH_PATHS", "data, content,"),
+                ("PATH", "/usr/bin"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.build, "dist");
+        assert!(!config.minify);
+        assert_eq!(config.port_ws, 4001);
+        assert_eq!(config.watch_paths, ["data", "content"]);
+
+        assert!(matches!(
+            config.apply_vars(vars(&[("UNREACT_MINIFY", "maybe")])),
+            Err(Error::ConfigEnv(var, _)) if var == "UNREACT_MINIFY",
+        ));
+        assert!(matches!(
+            config.apply_vars(vars(&[("UNREACT_BUIDL", "dist")])),
+            Err(Error::ConfigEnv(var, _)) if var == "UNREACT_BUIDL",
+        ));
+        assert!(config
+            .apply_vars(vars(&[("UNREACT_PORT", "70000")]))
+            .is_err());
+
+        // Values too large for toml only fail if a variable is set
+        let mut config = Config {
+            watch_debounce: u64::MAX,
+            ..Config::default()
+        };
+        assert!(config.apply_vars(vars(&[("PATH", "/usr/bin")])).is_ok());
+        assert!(matches!(
+            config.apply_vars(vars(&[("UNREACT_MINIFY", "0")])),
+            Err(Error::ConfigEnv(..)),
+        ));
+    }
+
+    #[test]
+    fn validate_works() {
+        assert!(Config::default().validate().is_ok());
+
+        let invalid = [
+            Config {
+                build: "./".to_string(),
+                ..Config::default()
+            },
+            Config {
+                build: "assets".to_string(),
+                ..Config::default()
+            },
+            Config {
+                templates: "".to_string(),
+                ..Config::default()
+            },
+            Config {
+                port_ws: Config::default().port,
+                ..Config::default()
+            },
+            Config {
+                style_output: "style.css".to_string(),
+                ..Config::default()
+            },
+            Config {
+                build_styles: "/assets/css/".to_string(),
+                ..Config::default()
+            },
+            Config {
+                build_public: "../static".to_string(),
+                ..Config::default()
+            },
+            Config {
+                build_public: "styles".to_string(),
+                ..Config::default()
+            },
+            Config {
+                base_path: "/project/".to_string(),
+                ..Config::default()
+            },
+            Config {
+                gzip_level: 10,
+                ..Config::default()
+            },
+            Config {
+                brotli_level: 12,
+                ..Config::default()
+            },
+            Config {
+                build_styles: "assets".to_string(),
+                build_public: "assets/public".to_string(),
+                ..Config::default()
+            },
+            Config {
+                build_styles: "public/css".to_string(),
+                ..Config::default()
+            },
+            Config {
+                targets: vec!["netscape >= 4".to_string()],
+                ..Config::default()
+            },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+
+        let config = Config {
+            build: "./assets-build/".to_string(),
+            build_styles: "assets/css".to_string(),
+            build_public: "assets/static".to_string(),
+            base_path: "org/project".to_string(),
+            ..Config::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+}
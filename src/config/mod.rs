@@ -0,0 +1,364 @@
+/// Load config from files and environment variables
+mod load;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Port, DEFAULT_COMPRESS_MIN_SIZE, DEFAULT_IGNORE, DEFAULT_PORT, DEFAULT_PORT_WS,
+    DEFAULT_STYLE_OUTPUT, DEFAULT_WATCH_DEBOUNCE,
+};
+
+/// Configuration struct for `Unreact`
+///
+/// Use `Config::default()` for default values, or `Config::load()` to read `unreact.toml` (see [`load`](struct.Config.html#method.load))
+///
+/// ## Summary
+///
+/// - `strict`: Whether [`Handlebars`](handlebars) uses 'strict mode'
+/// - `minify`: Whether output files should be minified
+/// - `ignore`: Patterns of source files to ignore
+/// - `gitignore`: Whether to also ignore files matching `.gitignore`
+/// - `source_maps`: Whether to write source maps for styles (always in *dev mode*)
+/// - `approximate_source_maps`: Whether source maps for styles have approximate mappings
+/// - `purge`: Whether to remove unused rules from styles, using the rendered pages
+/// - `purge_safelist`: Classes, ids, and tags to always keep when purging styles
+/// - `critical_css`: Whether to inline the styles used by each page, and load stylesheets without blocking
+/// - `targets`: Browser targets for vendor prefixes and lowering modern CSS - Only used with `"prefix"` feature
+/// - `compress`: Whether to write gzip and brotli compressed copies of text files - Only used with `"compress"` feature
+/// - `compress_min_size`: Minimum size of files to compress, in bytes - Only used with `"compress"` feature
+/// - `gzip_level`: Compression level for gzip (`0` to `9`) - Only used with `"compress"` feature
+/// - `brotli_level`: Compression level for brotli (`0` to `11`) - Only used with `"compress"` feature
+/// - `drafts`: Whether to build draft routes
+/// - `verbose`: Whether to print every file written
+/// - `report`: Path to write a JSON report of files produced by each compile
+/// - `check_links`: Whether broken internal links between pages and files are warnings, errors, or not checked
+///
+/// Folders:
+///
+/// - `build`: Output folder for built files
+/// - `templates`: Source folder for template files
+/// - `styles`: Source folder for style files
+/// - `public`: Source folder for static public files
+///
+/// - `style_paths`: Extra folders to resolve `@use`, `@forward`, and `@import` in styles
+/// - `style_output`: Output path of each stylesheet, such as `{name}.css`
+///
+/// Build Subfolders (also used as url paths):
+///
+/// - `build_styles`: Subfolder of build directory for compiled stylesheets
+/// - `build_public`: Subfolder of build directory for copied public files
+///
+/// - `base_path`: Path of site under domain, such as `project` for `https://org.github.io/project/`
+/// - `rewrite_links`: Whether to prepend `base_path` to root-relative links, otherwise they are an error
+/// - `relative_urls`: Whether the `URL` and `path` helpers return paths relative to each page, for portable builds
+///
+/// Development Options:
+///
+/// - `port`: Port to serve *dev server* on - Only used with `"dev"` feature
+/// - `port_ws`: Port to serve *dev server* **websockets** on - Only used with `"watch"` feature
+/// - `watch`: Whether to watch files for changes - Only used with `"watch"` feature
+/// - `watch_debounce`: Time to wait for more file changes before recompiling - Only used with `"watch"` feature
+/// - `watch_paths`: Extra files or folders to watch, such as data files - Only used with `"watch"` feature
+/// - `serve_compressed`: Whether to serve compressed copies of files, if the client accepts them - Only used with `"dev"` feature
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Output folder for built files
+    ///
+    /// Overridden with DEV_BUILD_DIR if in dev mode
+    ///
+    /// Files are written to a temporary folder next to it (such as `build.unreact-new`), which is renamed to the build folder once every file is written.
+    /// The previous build is kept if compiling or writing fails.
+    /// The swap is two renames, so the build folder briefly does not exist while it is replaced
+    ///
+    /// See `build_styles` and `build_public` for subfolders
+    ///
+    /// Default: `build` (or `.devbuild` in dev mode)
+    pub build: String,
+    /// Source folder for template files
+    ///
+    /// Default: `templates`
+    pub templates: String,
+    /// Source folder for style files
+    ///
+    /// Default: `styles`
+    pub styles: String,
+    /// Source folder for static public files
+    ///
+    /// Default: `public`
+    pub public: String,
+    /// Extra folders to resolve `@use`, `@forward`, and `@import` rules in styles
+    ///
+    /// Paths are resolved relative to the style file first, then the styles source folder, then these folders in order
+    ///
+    /// Default: Empty
+    pub style_paths: Vec<String>,
+    /// Output path of each stylesheet, relative to `build_styles` in build directory
+    ///
+    /// `{name}` is replaced with the name of the style file, without the file extension, such as `scoped/stylish`.
+    /// The `CSS` partial and `STYLE` helper use this path
+    ///
+    /// Default: `{name}/style.css`
+    pub style_output: String,
+    /// Subfolder of build directory for compiled stylesheets, such as `assets/css`
+    ///
+    /// Also the url path of stylesheets, as used by the `CSS` partial, `STYLE` helper, and *dev server*
+    ///
+    /// Default: `styles`
+    pub build_styles: String,
+    /// Subfolder of build directory for files copied from the `public` source folder, such as `assets/static`
+    ///
+    /// Also the url path of public files, as used by the `ICON` partial, `PUBLIC` helper, and *dev server*
+    ///
+    /// Must not be the same as `build_styles`, or inside it (or contain it)
+    ///
+    /// Default: `public`
+    pub build_public: String,
+    /// Path of site under domain, for sites deployed under a subdirectory, such as `project` for `https://org.github.io/project/`
+    ///
+    /// The `path` helper prepends this to paths, and the *dev server* serves the site under this path.
+    /// Root-relative links in pages (such as `href="/about"`) are rewritten or are an error, depending on `rewrite_links`
+    ///
+    /// Must not have leading or trailing slashes
+    ///
+    /// Default: Empty (site is at root of domain)
+    pub base_path: String,
+    /// Whether to prepend `base_path` to root-relative links in pages, such as `href="/about"` or `src="/public/image.png"`
+    ///
+    /// Links in `href`, `src`, and `action` attributes of tags are checked, but not in text, comments, or `script`, `style`, `pre`, `code`, and `textarea` elements.
+    /// If `false`, root-relative links which do not start with `base_path` are an error.
+    /// Only used if `base_path` is not empty
+    ///
+    /// Default: `true`
+    pub rewrite_links: bool,
+    /// Whether the `URL` and `path` helpers (and inbuilt partials) return paths relative to each page, such as `../../styles/global/style.css`
+    ///
+    /// Built files can be opened from any location, including `file://`, as the `path` helper adds `index.html` to folder routes (such as `../about/index.html`).
+    /// Links written with the `URL` helper (such as `{{URL}}about`) are not changed, so should include `index.html`.
+    /// The url given to the app is not used, and `base_path` does not affect these helpers.
+    /// Links in the `404` page may not work, as it is served at any path.
+    /// Not used in *dev mode*
+    ///
+    /// Default: `false`
+    pub relative_urls: bool,
+
+    /// Whether [`Handlebars`](handlebars) uses 'strict mode'
+    ///
+    /// If `true`, undefined variables and partials throw an error
+    pub strict: bool,
+    /// Whether output files should be minified
+    ///
+    /// Only affects `html` and `css` output files
+    pub minify: bool,
+    /// Patterns of source files to ignore, using `.gitignore` syntax
+    ///
+    /// Ignored files are not loaded as templates or styles, not copied to the build directory, and do not trigger recompiles in *dev mode*.
+    /// Patterns are relative to the working directory
+    ///
+    /// Default: Common editor and system files, such as `*.swp`, `*~`, and `.DS_Store`
+    pub ignore: Vec<String>,
+    /// Whether to also ignore source files matching the `.gitignore` file in the working directory
+    ///
+    /// Patterns in `ignore` take precedence, so files can be un-ignored with `!`
+    ///
+    /// Default: `true`
+    pub gitignore: bool,
+    /// Whether to write source maps for styles, as `style.css.map` next to each `style.css` (or other output file)
+    ///
+    /// Source maps are always written in *dev mode*.
+    /// The SCSS compiler does not track source positions, so source maps only include the source file, without mappings,
+    /// unless `approximate_source_maps` is enabled
+    ///
+    /// Default: `false`
+    pub source_maps: bool,
+    /// Whether source maps for styles have approximate mappings, by matching selectors and properties to source lines
+    ///
+    /// Mappings may point to the wrong line (such as for selectors repeated in partials), so a warning is added to the build report.
+    /// Only used if source maps are written (see `source_maps`)
+    ///
+    /// Default: `false`
+    pub approximate_source_maps: bool,
+    /// Whether to remove unused rules from styles, using the classes, ids, and tags in every rendered page
+    ///
+    /// Selectors for elements created by scripts should be added to `purge_safelist`
+    ///
+    /// Default: `false`
+    pub purge: bool,
+    /// Class names, ids, or tag names to always keep when purging styles, such as `"active"` or `"js-*"`
+    ///
+    /// `*` matches any characters
+    ///
+    /// Default: Empty
+    pub purge_safelist: Vec<String>,
+    /// Whether to inline the style rules used by each page into the page, and load the full stylesheets without blocking rendering
+    ///
+    /// The `CSS` partial writes a `<style>` tag with the rules of the stylesheet which match the classes, ids, and tags in the page,
+    /// and a `<link rel="preload">` tag which applies the full stylesheet once loaded
+    ///
+    /// Default: `false`
+    pub critical_css: bool,
+    /// Browser targets for styles, as browserslist-style queries, such as `"safari >= 12"` or `"chrome 80"`
+    ///
+    /// If not empty, modern syntax (such as nesting and `color-mix`) is lowered for the targets.
+    /// If `minify` is also enabled, vendor prefixes are added and logical properties are lowered, as these are done while merging rules.
+    /// The lowest version is used for each browser
+    ///
+    /// Only used with `"prefix"` feature, but must be defined always. Targets are validated without the feature too
+    ///
+    /// Default: Empty (no post-processing)
+    pub targets: Vec<String>,
+    /// Whether to write gzip (`.gz`) and brotli (`.br`) compressed copies next to output files, for hosts which serve them
+    ///
+    /// Only HTML, CSS, JS, SVG, JSON, and XML files are compressed, which are at least `compress_min_size` bytes.
+    /// In *dev mode*, files are only compressed if `serve_compressed` is enabled
+    ///
+    /// Only used with `"compress"` feature, but must be defined always
+    ///
+    /// Default: `false`
+    pub compress: bool,
+    /// Minimum size of files to compress, in bytes
+    ///
+    /// Smaller files are not worth compressing
+    ///
+    /// Only used with `"compress"` feature, but must be defined always
+    ///
+    /// Default: `1024`
+    pub compress_min_size: usize,
+    /// Compression level for gzip, from `0` (fastest) to `9` (smallest)
+    ///
+    /// Only used with `"compress"` feature, but must be defined always
+    ///
+    /// Default: `9`
+    pub gzip_level: u32,
+    /// Compression level for brotli, from `0` (fastest) to `11` (smallest)
+    ///
+    /// Only used with `"compress"` feature, but must be defined always
+    ///
+    /// Default: `11`
+    pub brotli_level: u32,
+    /// Whether to build draft routes, created with [`Unreact::draft`](struct.Unreact.html#method.draft)
+    ///
+    /// Default: `false`
+    pub drafts: bool,
+    /// Whether to print the path of every file written
+    ///
+    /// Default: `false`
+    pub verbose: bool,
+    /// Path to write a JSON report of files produced by each compile, relative to working directory, such as `report.json`
+    ///
+    /// See [`BuildReport`](struct.BuildReport.html)
+    ///
+    /// Default: Empty (no report written)
+    pub report: String,
+    /// Whether to check internal links (`href` and `src` attributes) in every HTML file after compiling, including `#fragment` anchors
+    ///
+    /// Broken links are added to the warnings of the [`BuildReport`](struct.BuildReport.html) with `"warn"`,
+    /// or fail the compile with `"error"`. Use `"off"` to not check links
+    ///
+    /// Default: `"warn"`
+    pub check_links: LinkCheck,
+
+    /// Port for main *dev server* to be hosted on
+    ///
+    /// Only used with `"dev"` feature, but must be defined always
+    pub port: Port,
+    /// Port for websocket server to be hosted on
+    ///
+    /// Only used with `"watch"` feature, but must be defined always
+    pub port_ws: Port,
+    /// Whether to watch source files for changes in *dev mode*, and recompile and reload clients
+    ///
+    /// If `false`, the dev server is opened without watching files
+    ///
+    /// Only used with `"watch"` feature, but must be defined always
+    ///
+    /// Default: `true`
+    pub watch: bool,
+    /// Time to wait, in milliseconds, for more file changes before recompiling
+    ///
+    /// Changes made in quick succession (such as an editor saving multiple files) are recompiled together
+    ///
+    /// Only used with `"watch"` feature, but must be defined always
+    ///
+    /// Default: `300`
+    pub watch_debounce: u64,
+    /// Extra files or folders to watch for changes, such as data files or a content folder
+    ///
+    /// Changes to these paths recompile the app, and rerun the route builder (see [`Unreact::route_builder`](struct.Unreact.html#method.route_builder))
+    ///
+    /// Only used with `"watch"` feature, but must be defined always
+    ///
+    /// Default: Empty
+    pub watch_paths: Vec<String>,
+    /// Whether the *dev server* serves compressed copies of files (`.br` or `.gz`, written with `compress`), if the client accepts them
+    ///
+    /// Use to check that compressed files are correct, before deploying to a host which serves them
+    ///
+    /// Only used with `"dev"` feature, but must be defined always
+    ///
+    /// Default: `false`
+    pub serve_compressed: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            //? Move to const in lib.rs ?
+            build: "build".to_string(),
+            templates: "assets/templates".to_string(),
+            styles: "assets/styles".to_string(),
+            public: "assets/public".to_string(),
+            style_paths: Vec::new(),
+            style_output: DEFAULT_STYLE_OUTPUT.to_string(),
+            build_styles: "styles".to_string(),
+            build_public: "public".to_string(),
+            base_path: String::new(),
+            rewrite_links: true,
+            relative_urls: false,
+
+            strict: false,
+            minify: true,
+            ignore: DEFAULT_IGNORE
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            gitignore: true,
+            source_maps: false,
+            approximate_source_maps: false,
+            purge: false,
+            purge_safelist: Vec::new(),
+            critical_css: false,
+            targets: Vec::new(),
+            compress: false,
+            compress_min_size: DEFAULT_COMPRESS_MIN_SIZE,
+            gzip_level: 9,
+            brotli_level: 11,
+            drafts: false,
+            verbose: false,
+            report: String::new(),
+            check_links: LinkCheck::Warn,
+
+            port: DEFAULT_PORT,
+            port_ws: DEFAULT_PORT_WS,
+            watch: true,
+            watch_debounce: DEFAULT_WATCH_DEBOUNCE,
+            watch_paths: Vec::new(),
+            serve_compressed: false,
+        }
+    }
+}
+
+/// How broken internal links are handled after compiling
+///
+/// See `Config::check_links`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkCheck {
+    /// Do not check links
+    Off,
+    /// Add broken links to warnings of build report
+    #[default]
+    Warn,
+    /// Fail compile if any link is broken
+    Error,
+}
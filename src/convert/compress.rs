@@ -0,0 +1,108 @@
+use std::io::{self, Write};
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{files::get_extension, Config, Error, OutputMap};
+
+/// File extensions of outputs to compress
+const COMPRESS_EXTENSIONS: &[&str] = &["html", "css", "js", "mjs", "svg", "json", "xml"];
+
+/// Add gzip (`.gz`) and brotli (`.br`) compressed copies of text outputs, next to each file
+///
+/// Only files with a compressible extension, which are at least `compress_min_size` bytes, are compressed
+pub fn compress_outputs(outputs: &mut OutputMap, config: &Config) -> Result<(), Error> {
+    let mut compressed = Vec::new();
+
+    for (path, content) in outputs.iter() {
+        if is_compressible(path, content.len(), config) {
+            compressed.extend(compress_file(path, content, config)?);
+        }
+    }
+
+    outputs.extend(compressed);
+    Ok(())
+}
+
+/// Whether a file should be compressed, by file extension and size in bytes
+pub fn is_compressible(path: &str, size: usize, config: &Config) -> bool {
+    COMPRESS_EXTENSIONS.contains(&get_extension(path)) && size >= config.compress_min_size
+}
+
+/// Create gzip (`.gz`) and brotli (`.br`) compressed copies of a file, by path next to the file
+pub fn compress_file(
+    path: &str,
+    content: &[u8],
+    config: &Config,
+) -> Result<[(String, Vec<u8>); 2], Error> {
+    let gzip = try_unwrap!(
+        gzip(content, config.gzip_level),
+        else Err(err) => return fail!(Compress, path.to_string(), err),
+    );
+    let brotli = try_unwrap!(
+        brotli(content, config.brotli_level),
+        else Err(err) => return fail!(Compress, path.to_string(), err),
+    );
+
+    Ok([(format!("{path}.gz"), gzip), (format!("{path}.br"), brotli)])
+}
+
+/// Compress with gzip, with level from `0` to `9`
+fn gzip(content: &[u8], level: u32) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// Compress with brotli, with level from `0` to `11`
+fn brotli(content: &[u8], level: u32) -> io::Result<Vec<u8>> {
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: level as i32,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    brotli::BrotliCompress(&mut &content[..], &mut output, &params)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn compress_outputs_works() {
+        let html = "<p>Hello world!</p>".repeat(100).into_bytes();
+
+        let mut outputs = OutputMap::new();
+        outputs.insert("index.html".to_string(), html.clone());
+        outputs.insert("small.css".to_string(), b"p{color:red}".to_vec());
+        outputs.insert("public/image.png".to_string(), vec![0; 2048]);
+
+        compress_outputs(&mut outputs, &Config::default()).unwrap();
+
+        let keys: Vec<_> = outputs.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            [
+                "index.html",
+                "index.html.br",
+                "index.html.gz",
+                "public/image.png",
+                "small.css",
+            ]
+        );
+
+        let mut gzip = Vec::new();
+        flate2::read::GzDecoder::new(&outputs["index.html.gz"][..])
+            .read_to_end(&mut gzip)
+            .unwrap();
+        assert_eq!(gzip, html);
+
+        let mut brotli = Vec::new();
+        brotli::Decompressor::new(&outputs["index.html.br"][..], 4096)
+            .read_to_end(&mut brotli)
+            .unwrap();
+        assert_eq!(brotli, html);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use super::{process_css, purge_css, UsedSelectors};
+use crate::Error;
+
+/// Start of comment written by the `CSS` partial, followed by the stylesheet name, when inlining critical CSS
+const CRITICAL_MARKER: &str = "<!--unreact:critical ";
+
+/// Replace critical CSS markers in a page with the rules of each stylesheet used by the page
+///
+/// Rules are chosen by the classes, ids, and tags in the page (see [`purge_css`]), not by position in the page,
+/// so all rules for the page are inlined.
+/// Markers for unknown stylesheets are removed
+///
+/// - `styles`: Compiled CSS of each stylesheet, by name, before minifying
+pub fn inline_critical_css(
+    html: &str,
+    styles: &HashMap<String, String>,
+    safelist: &[String],
+    minify: bool,
+    targets: &[String],
+) -> Result<String, Error> {
+    let mut used = UsedSelectors::default();
+    used.scan(html);
+
+    replace_markers(html, |name| {
+        let Some(css) = styles.get(name) else {
+            return Ok(String::new());
+        };
+        let css = purge_css(css, &used, safelist);
+        let css = process_css(name, css, minify, targets)?;
+        if css.trim().is_empty() {
+            return Ok(String::new());
+        }
+        Ok(format!("<style>{css}</style>"))
+    })
+}
+
+/// Remove critical CSS markers from a page, without inlining any styles
+///
+/// Used for pages rendered without compiling styles
+pub fn strip_critical_markers(html: &str) -> Result<String, Error> {
+    replace_markers(html, |_| Ok(String::new()))
+}
+
+/// Replace each critical CSS marker in a page, with the output of `replace` for the stylesheet name
+fn replace_markers<F>(html: &str, mut replace: F) -> Result<String, Error>
+where
+    F: FnMut(&str) -> Result<String, Error>,
+{
+    let mut output = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find(CRITICAL_MARKER) {
+        output += &rest[..start];
+        let marker = &rest[start + CRITICAL_MARKER.len()..];
+
+        // Unclosed comment
+        let Some(end) = marker.find("-->") else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = marker[..end].trim();
+        rest = &marker[end + 3..];
+
+        output += &replace(name)?;
+    }
+
+    output += rest;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_critical_css_works() {
+        let mut styles = HashMap::new();
+        styles.insert(
+            "global".to_string(),
+            "body {\n  color: white;\n}\n.unused {\n  color: red;\n}\n".to_string(),
+        );
+
+        let html = "<html><head><!--unreact:critical global--><!--unreact:critical missing-->\
+            <link rel=\"preload\" /></head><body></body></html>";
+
+        assert_eq!(
+            inline_critical_css(html, &styles, &[], true, &[]).unwrap(),
+            "<html><head><style>body{color:white}</style><link rel=\"preload\" /></head><body></body></html>"
+        );
+    }
+
+    #[test]
+    fn strip_critical_markers_works() {
+        assert_eq!(
+            strip_critical_markers("<head><!--unreact:critical global--><link /></head>").unwrap(),
+            "<head><link /></head>"
+        );
+        assert_eq!(
+            strip_critical_markers("<p><!-- other --></p><!--unreact:critical open").unwrap(),
+            "<p><!-- other --></p><!--unreact:critical open"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use css_minify::optimizations as css_minify;
+
+use crate::Error;
+
+/// Post-process compiled CSS, and minify
+///
+/// With the `"prefix"` feature, and if any browser targets are given, vendor prefixes are added and modern syntax
+/// (such as nesting, `color-mix`, and logical properties) is lowered for the targets.
+/// This replaces the default minifier, when minifying.
+///
+/// Otherwise browser targets are ignored
+pub fn process_css(
+    name: &str,
+    css: String,
+    minify: bool,
+    // Only for "prefix" feature
+    #[allow(unused_variables)] targets: &[String],
+) -> Result<String, Error> {
+    // Prefix and lower for targets
+    #[cfg(feature = "prefix")]
+    {
+        if !targets.is_empty() {
+            return lower_css(name, &css, minify, targets);
+        }
+    }
+
+    // Minify
+    if minify {
+        return Ok(try_unwrap!(
+            css_minify::Minifier::default().minify(&css, css_minify::Level::Two),
+            else Err(err) => return fail!(CssMinify, name.to_string(), err.to_string()),
+        ));
+    }
+
+    // Don't minify
+    Ok(css)
+}
+
+/// Add vendor prefixes and lower modern syntax for browser targets, and minify
+#[cfg(feature = "prefix")]
+fn lower_css(name: &str, css: &str, minify: bool, targets: &[String]) -> Result<String, Error> {
+    use lightningcss::stylesheet::{MinifyOptions, ParserFlags, ParserOptions, PrinterOptions};
+    use lightningcss::{stylesheet::StyleSheet, targets::Targets};
+
+    let targets = Targets::from(target_browsers(targets)?);
+
+    let options = ParserOptions {
+        filename: name.to_string(),
+        flags: ParserFlags::NESTING,
+        ..ParserOptions::default()
+    };
+    let mut stylesheet = try_unwrap!(
+        StyleSheet::parse(css, options),
+        else Err(err) => return fail!(CssProcess, name.to_string(), err.to_string()),
+    );
+
+    // Transform and merge rules for targets (adding prefixes and lowering properties), only if minifying
+    if minify {
+        try_unwrap!(
+            stylesheet.minify(MinifyOptions {
+                targets,
+                ..MinifyOptions::default()
+            }),
+            else Err(err) => return fail!(CssProcess, name.to_string(), err.to_string()),
+        );
+    }
+
+    let output = try_unwrap!(
+        stylesheet.to_css(PrinterOptions {
+            minify,
+            targets,
+            ..PrinterOptions::default()
+        }),
+        else Err(err) => return fail!(CssProcess, name.to_string(), err.to_string()),
+    );
+
+    Ok(output.code)
+}
+
+/// Parse browserslist-style queries into browser names and versions
+///
+/// Supports queries such as `safari >= 12`, `ios_saf >= 12.2` or `chrome 80`, separated by commas or as separate items.
+/// Browser aliases (such as `ff` and `ios`) are converted to the full name
+///
+/// Always used to validate config, even without the `"prefix"` feature
+pub fn parse_targets(targets: &[String]) -> Result<Vec<(&'static str, u32)>, Error> {
+    let mut browsers = Vec::new();
+
+    for query in targets.iter().flat_map(|target| target.split(',')) {
+        let query = query.trim();
+        if query.is_empty() {
+            continue;
+        }
+
+        // Browser name, optional `>=`, and version
+        let mut words = query.split_whitespace();
+        let (Some(browser), Some(version)) = (words.next(), words.next()) else {
+            return fail!(InvalidTarget, query.to_string());
+        };
+        let version = match version {
+            ">=" => words.next(),
+            _ => Some(version),
+        };
+        let (Some(version), None) = (version.and_then(parse_version), words.next()) else {
+            return fail!(InvalidTarget, query.to_string());
+        };
+
+        let browser = match browser.to_lowercase().as_str() {
+            "android" => "android",
+            "chrome" => "chrome",
+            "edge" => "edge",
+            "firefox" | "ff" => "firefox",
+            "ie" | "explorer" => "ie",
+            "ios_saf" | "ios" => "ios_saf",
+            "opera" => "opera",
+            "safari" => "safari",
+            "samsung" => "samsung",
+            _ => return fail!(InvalidTarget, query.to_string()),
+        };
+
+        browsers.push((browser, version));
+    }
+
+    Ok(browsers)
+}
+
+/// Convert browser targets into minimum browser versions for `lightningcss`
+///
+/// If a browser is given multiple times, the lowest version is used
+#[cfg(feature = "prefix")]
+fn target_browsers(targets: &[String]) -> Result<lightningcss::targets::Browsers, Error> {
+    let mut browsers = lightningcss::targets::Browsers::default();
+
+    for (browser, version) in parse_targets(targets)? {
+        let field = match browser {
+            "android" => &mut browsers.android,
+            "chrome" => &mut browsers.chrome,
+            "edge" => &mut browsers.edge,
+            "firefox" => &mut browsers.firefox,
+            "ie" => &mut browsers.ie,
+            "ios_saf" => &mut browsers.ios_saf,
+            "opera" => &mut browsers.opera,
+            "safari" => &mut browsers.safari,
+            "samsung" => &mut browsers.samsung,
+            _ => unreachable!("Browser names are checked when parsing"),
+        };
+
+        // Keep lowest version
+        *field = Some(field.map_or(version, |other| other.min(version)));
+    }
+
+    Ok(browsers)
+}
+
+/// Parse a version such as `12` or `12.1.2`, in the format used by browser targets
+fn parse_version(version: &str) -> Option<u32> {
+    let mut parts = version.split('.');
+    let major: u32 = parts.next()?.parse().ok()?;
+    let minor: u32 = parts.next().map_or(Some(0), |part| part.parse().ok())?;
+    let patch: u32 = parts.next().map_or(Some(0), |part| part.parse().ok())?;
+    if parts.next().is_some() || minor > 255 || patch > 255 {
+        return None;
+    }
+    Some(major << 16 | minor << 8 | patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_css_works() {
+        let css = "body {\n  background-color: black;\n}\nbody p {\n  color: white;\n}\n";
+
+        assert_eq!(
+            process_css("no_name", css.to_string(), false, &[]).unwrap(),
+            css
+        );
+        assert_eq!(
+            process_css("no_name", css.to_string(), true, &[]).unwrap(),
+            "body{background:black}body p{color:white}"
+        );
+    }
+
+    #[test]
+    fn parse_targets_works() {
+        let browsers = parse_targets(&[
+            "safari >= 12, ios >= 12.2".to_string(),
+            "Chrome 80".to_string(),
+        ])
+        .expect("Should have parsed");
+
+        assert_eq!(
+            browsers,
+            [
+                ("safari", 12 << 16),
+                ("ios_saf", 12 << 16 | 2 << 8),
+                ("chrome", 80 << 16),
+            ]
+        );
+
+        assert!(parse_targets(&["netscape >= 4".to_string()]).is_err());
+        assert!(parse_targets(&["safari >=".to_string()]).is_err());
+        assert!(parse_targets(&["safari >= 12 13".to_string()]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "prefix")]
+    fn target_browsers_works() {
+        let browsers = target_browsers(&[
+            "safari >= 12, ios_saf >= 12.2".to_string(),
+            "Chrome 80".to_string(),
+            "safari 11.1".to_string(),
+        ])
+        .expect("Should have parsed");
+
+        assert_eq!(browsers.safari, Some(11 << 16 | 1 << 8));
+        assert_eq!(browsers.ios_saf, Some(12 << 16 | 2 << 8));
+        assert_eq!(browsers.chrome, Some(80 << 16));
+        assert_eq!(browsers.firefox, None);
+    }
+
+    #[test]
+    #[cfg(feature = "prefix")]
+    fn lowering_works() {
+        let css = "\
+.a {
+  user-select: none;
+  margin-inline-start: 1px;
+  color: color-mix(in srgb, red 50%, blue);
+
+  & b {
+    color: red;
+  }
+}
+";
+        let targets = ["safari >= 12".to_string()];
+        let lowered = process_css("no_name", css.to_string(), true, &targets).unwrap();
+
+        assert!(lowered.contains("-webkit-user-select:none"));
+        assert!(!lowered.contains("color-mix"));
+        assert!(!lowered.contains("margin-inline-start"));
+        assert!(lowered.contains(".a b{color:red}"));
+
+        // Not minified, so rules are not merged or transformed
+        let readable = process_css("no_name", css.to_string(), false, &targets).unwrap();
+        assert!(readable.contains(".a {\n  user-select: none;\n"));
+        assert!(readable.contains(".a b {\n  color: red;\n}"));
+        assert!(!readable.contains("color-mix"));
+    }
+}
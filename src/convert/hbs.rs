@@ -1,28 +1,44 @@
+use std::collections::HashSet;
+
 use handlebars::{Context, Handlebars, Helper, HelperResult, JsonRender, Output, RenderContext};
 
-use crate::{Error, FileMap, Object, Page, Port, Value};
+use crate::{files::get_extension, Config, Error, FileMap, Object, Page, RouteMap, Value};
 
 /// Registry all [`Handlebars`](handlebars) partials, and helpers
 ///
 /// NOT Templates
-pub fn register_inbuilt(registry: &mut Handlebars, url: &str) -> Result<(), Error> {
+pub fn register_inbuilt(
+    registry: &mut Handlebars,
+    url: &str,
+    config: &Config,
+) -> Result<(), Error> {
     // Register inbuilt templates (partials)
-    register_partials(registry)?;
+    register_partials(registry, config)?;
     // Register inbuilt helpers
-    register_helpers(registry, url);
+    register_helpers(registry, url, config);
     Ok(())
 }
 
 /// Render a page, using either a [`Handlebars`](handlebars) template or a raw string, and minify
+///
+/// With `relative_urls` in config (not in *dev mode*), the `URL` and `path` helpers are registered again for the route,
+/// to return paths relative to the page, with `index.html` for folder routes
+///
+/// Returns rendered page, and size of page in bytes before minifying
 pub(crate) fn render_page(
     registry: &mut Handlebars,
+    route: &str,
     page: &Page,
     globals: Object,
-    minify: bool,
-    // Only for "dev" feature
-    #[allow(unused_variables)] is_dev: bool,
-    #[allow(unused_variables)] port_ws: Port,
-) -> Result<String, Error> {
+    config: &Config,
+    is_dev: bool,
+) -> Result<(String, usize), Error> {
+    // Use urls relative to page
+    if config.relative_urls && !is_dev {
+        let root = relative_root(route);
+        register_url_helpers(registry, &format!("{root}/"), &root, true);
+    }
+
     let mut rendered = match page {
         Page::Raw(page) => page.to_string(),
 
@@ -39,8 +55,10 @@ pub(crate) fn render_page(
         }
     };
 
+    let raw_size = rendered.len();
+
     // Minify before adding dev script
-    if minify {
+    if config.minify {
         let config = minify_html::Cfg {
             do_not_minify_doctype: true,
             keep_comments: true,
@@ -59,10 +77,10 @@ pub(crate) fn render_page(
     #[cfg(feature = "dev")]
     if is_dev {
         rendered += "\n\n";
-        rendered += &crate::server::dev_script(port_ws);
+        rendered += &crate::server::dev_script(config);
     }
 
-    Ok(rendered)
+    Ok((rendered, raw_size))
 }
 
 /// Register custom [`Handlebars`](handlebars) templates onto registry
@@ -92,9 +110,18 @@ const PARTIALS: &[(&str, &str)] = &[
     ("META_EXTRA", include_str!("partials/META_EXTRA.hbs")),
 ];
 
+/// Css partial for inlining critical css, replacing `CSS`
+const CSS_CRITICAL: &str = include_str!("partials/CSS_CRITICAL.hbs");
+
 /// Register const inbuilt [`Handlebars`](handlebars) templates (partials) onto registry
-fn register_partials(registry: &mut Handlebars) -> Result<(), Error> {
+fn register_partials(registry: &mut Handlebars, config: &Config) -> Result<(), Error> {
     for (name, template) in PARTIALS {
+        // Inline critical css, and load stylesheet without blocking
+        let template = match *name {
+            "CSS" if config.critical_css => CSS_CRITICAL,
+            _ => template,
+        };
+
         try_unwrap!(
             registry.register_partial(name, template),
             else Err(err) => return fail!(RegisterInbuiltTemplate, name.to_string(), Box::new(err)),
@@ -105,19 +132,50 @@ fn register_partials(registry: &mut Handlebars) -> Result<(), Error> {
 }
 
 /// Registers some as [`Handlebars`](handlebars) helpers, including `url`
-fn register_helpers(registry: &mut Handlebars, url: &str) {
-    // Url helper, returns url given
-    let url = url.to_string();
-    let closure = move |_: &Helper,
+fn register_helpers(registry: &mut Handlebars, url: &str, config: &Config) {
+    // Url and path helpers, with base path prepended to paths
+    let path_prefix = match config.base_path.as_str() {
+        "" => String::new(),
+        base_path => format!("/{base_path}"),
+    };
+    register_url_helpers(registry, url, &path_prefix, false);
+
+    // Style helper, returns output path of stylesheet with given name, relative to build directory
+    let build_styles = config.build_styles.clone();
+    let style_output = config.style_output.clone();
+    let closure = move |helper: &Helper,
                         _: &Handlebars,
                         _: &Context,
                         _: &mut RenderContext,
                         out: &mut dyn Output|
           -> HelperResult {
-        out.write(&url)?;
+        let name = helper
+            .param(0)
+            .map(|param| param.value().render())
+            .unwrap_or_default();
+
+        out.write(&style_path(&build_styles, &style_output, &name))?;
         Ok(())
     };
-    registry.register_helper("URL", Box::new(closure));
+    registry.register_helper("STYLE", Box::new(closure));
+
+    // Public helper, returns path of public file with given path (or public folder if none given), relative to build directory
+    let build_public = config.build_public.clone();
+    let closure = move |helper: &Helper,
+                        _: &Handlebars,
+                        _: &Context,
+                        _: &mut RenderContext,
+                        out: &mut dyn Output|
+          -> HelperResult {
+        let path = helper
+            .param(0)
+            .map(|param| param.value().render())
+            .unwrap_or_default();
+
+        out.write(&format!("{build_public}/{path}"))?;
+        Ok(())
+    };
+    registry.register_helper("PUBLIC", Box::new(closure));
 
     // Concat helper, concatenates strings
     let closure = |helper: &Helper,
@@ -136,3 +194,324 @@ fn register_helpers(registry: &mut Handlebars, url: &str) {
     };
     registry.register_helper("concat", Box::new(closure));
 }
+
+/// Register [`Handlebars`](handlebars) helpers which depend on location of site or page
+///
+/// - `URL` helper returns `url`
+/// - `path` helper returns path given, with `path_prefix` prepended, such as `/project/about` for `about`
+///
+/// If `index_files` is `true`, the `path` helper adds `index.html` to paths of folder routes, such as `../about/index.html`
+fn register_url_helpers(
+    registry: &mut Handlebars,
+    url: &str,
+    path_prefix: &str,
+    index_files: bool,
+) {
+    // Url helper, returns url given
+    let url = url.to_string();
+    let closure = move |_: &Helper,
+                        _: &Handlebars,
+                        _: &Context,
+                        _: &mut RenderContext,
+                        out: &mut dyn Output|
+          -> HelperResult {
+        out.write(&url)?;
+        Ok(())
+    };
+    registry.register_helper("URL", Box::new(closure));
+
+    // Path helper, returns path given with prefix
+    let path_prefix = path_prefix.to_string();
+    let closure = move |helper: &Helper,
+                        _: &Handlebars,
+                        _: &Context,
+                        _: &mut RenderContext,
+                        out: &mut dyn Output|
+          -> HelperResult {
+        let path = helper
+            .param(0)
+            .map(|param| param.value().render())
+            .unwrap_or_default();
+        let path = path.trim_start_matches('/');
+
+        if index_files {
+            out.write(&format!("{path_prefix}/{}", index_file(path)))?;
+        } else {
+            out.write(&format!("{path_prefix}/{path}"))?;
+        }
+        Ok(())
+    };
+    registry.register_helper("path", Box::new(closure));
+}
+
+/// Add `index.html` to a path of a folder route, such as `about/index.html` for `about` or `about/`
+///
+/// Paths of files (with a file extension) are unchanged. Any `?query` or `#fragment` is kept after the file name
+fn index_file(path: &str) -> String {
+    let (path, rest) = path.split_at(path.find(['?', '#']).unwrap_or(path.len()));
+    if !get_extension(path).is_empty() {
+        return format!("{path}{rest}");
+    }
+
+    match path.trim_end_matches('/') {
+        "" => format!("index.html{rest}"),
+        path => format!("{path}/index.html{rest}"),
+    }
+}
+
+/// Get path from a page of a route to the build directory, without trailing slash, such as `../..` for `blog/post`
+///
+/// The `404` route is written to build directory, not a subfolder
+fn relative_root(route: &str) -> String {
+    let depth = match route {
+        "404" => 0,
+        _ => route.split('/').filter(|part| !part.is_empty()).count(),
+    };
+
+    match depth {
+        0 => ".".to_string(),
+        _ => vec![".."; depth].join("/"),
+    }
+}
+
+/// Get the output path of a stylesheet, relative to build directory
+///
+/// - `build_styles`: Styles subfolder of build directory
+/// - `style_output`: Output path of stylesheet, relative to `build_styles`
+pub fn style_path(build_styles: &str, style_output: &str, name: &str) -> String {
+    format!("{build_styles}/{}", style_output.replace("{name}", name))
+}
+
+/// Get the routes which use any of the changed templates, directly or through partials
+///
+/// Returns `None` if any route may be affected, as a template uses a dynamic partial name
+#[cfg_attr(not(feature = "watch"), allow(dead_code))]
+pub(crate) fn routes_using_templates(
+    routes: &RouteMap,
+    templates: &FileMap,
+    changed: &[String],
+) -> Option<Vec<String>> {
+    let mut affected = Vec::new();
+
+    for (path, page) in routes {
+        // Raw pages do not use templates
+        let Page::Template { template, .. } = page else {
+            continue;
+        };
+
+        let dependencies = template_dependencies(template, templates)?;
+        if changed.iter().any(|name| dependencies.contains(name)) {
+            affected.push(path.clone());
+        }
+    }
+
+    Some(affected)
+}
+
+/// Get the names of a template and all partials it uses, recursively
+///
+/// Returns `None` if a dynamic partial name is used
+fn template_dependencies(template: &str, templates: &FileMap) -> Option<HashSet<String>> {
+    let mut dependencies = HashSet::new();
+    let mut unchecked = vec![template.to_string()];
+
+    while let Some(name) = unchecked.pop() {
+        if !dependencies.insert(name.clone()) {
+            continue;
+        }
+        // Template may not exist, or is inbuilt partial
+        if let Some(content) = templates.get(&name) {
+            unchecked.extend(partial_names(content)?);
+        }
+    }
+
+    Some(dependencies)
+}
+
+/// Get the names of partials used in a template, with `{{> name}}` or `{{#> name}}`
+///
+/// Returns `None` if a dynamic partial name is used, such as `{{> (lookup . 'name')}}`
+fn partial_names(template: &str) -> Option<Vec<String>> {
+    let mut names = Vec::new();
+
+    for (i, _) in template.match_indices("{{") {
+        // Skip whitespace control and block characters
+        let rest = template[i + 2..].trim_start_matches(['~', '#']);
+        let Some(rest) = rest.strip_prefix('>') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+
+        // Dynamic partial name
+        if rest.starts_with('(') {
+            return None;
+        }
+
+        let name: String = rest
+            .chars()
+            .take_while(|ch| !ch.is_whitespace() && !matches!(ch, '}' | '~'))
+            .collect();
+        let name = name.trim_matches(['"', '\'']);
+
+        // Skip `@partial-block` and similar
+        if !name.is_empty() && !name.starts_with('@') {
+            names.push(name.to_string());
+        }
+    }
+
+    Some(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_names_works() {
+        assert_eq!(
+            partial_names("{{> boilerplate}} {{~> other/article a=1 ~}} {{#> layout}}x{{/layout}}"),
+            Some(vec![
+                "boilerplate".to_string(),
+                "other/article".to_string(),
+                "layout".to_string(),
+            ])
+        );
+        assert_eq!(
+            partial_names("{{> \"quoted\"}} {{> @partial-block}} {{foo}}"),
+            Some(vec!["quoted".to_string()])
+        );
+        assert_eq!(partial_names("{{> (lookup . 'name')}}"), None);
+    }
+
+    #[test]
+    fn css_partial_works() {
+        let config = Config {
+            style_output: "{name}.css".to_string(),
+            ..Config::default()
+        };
+        let mut registry = Handlebars::new();
+        register_inbuilt(&mut registry, "https://example.com/", &config).unwrap();
+
+        assert_eq!(
+            registry
+                .render_template("{{> CSS name=\"scoped/stylish\"}}", &Object::new())
+                .unwrap(),
+            "<link rel=\"stylesheet\" href=\"https://example.com/styles/scoped/stylish.css\" />"
+        );
+        assert_eq!(
+            style_path("styles", "{name}/style.css", "global"),
+            "styles/global/style.css"
+        );
+
+        let config = Config {
+            build_styles: "assets/css".to_string(),
+            build_public: "assets/static".to_string(),
+            base_path: "project".to_string(),
+            ..Config::default()
+        };
+        let mut registry = Handlebars::new();
+        register_inbuilt(&mut registry, "/", &config).unwrap();
+
+        assert_eq!(
+            registry
+                .render_template("{{path \"about\"}} {{path \"/\"}}", &Object::new())
+                .unwrap(),
+            "/project/about /project/"
+        );
+
+        assert_eq!(
+            registry
+                .render_template(
+                    "{{> CSS name=\"global\"}}{{> ICON name=\"icon.png\"}}",
+                    &Object::new()
+                )
+                .unwrap(),
+            "<link rel=\"stylesheet\" href=\"/assets/css/global/style.css\" />\
+            <link rel=\"shortcut icon\" href=\"/assets/static/icon.png\" />"
+        );
+    }
+
+    #[test]
+    fn relative_urls_work() {
+        assert_eq!(relative_root(""), ".");
+        assert_eq!(relative_root("404"), ".");
+        assert_eq!(relative_root("about"), "..");
+        assert_eq!(relative_root("/blog/post/"), "../..");
+
+        let config = Config {
+            relative_urls: true,
+            minify: false,
+            ..Config::default()
+        };
+        let mut registry = Handlebars::new();
+        register_inbuilt(&mut registry, "https://example.com/", &config).unwrap();
+        registry
+            .register_template_string(
+                "page",
+                "{{> CSS name=\"global\"}}<a href=\"{{path \"about\"}}\"></a>",
+            )
+            .unwrap();
+
+        let page = Page::Template {
+            template: "page".to_string(),
+            data: Object::new(),
+        };
+        let render = |registry: &mut Handlebars, route| {
+            render_page(registry, route, &page, Object::new(), &config, false)
+                .unwrap()
+                .0
+        };
+
+        assert_eq!(
+            render(&mut registry, "blog/post"),
+            "<link rel=\"stylesheet\" href=\"../../styles/global/style.css\" /><a href=\"../../about/index.html\"></a>"
+        );
+        assert_eq!(
+            render(&mut registry, ""),
+            "<link rel=\"stylesheet\" href=\"./styles/global/style.css\" /><a href=\"./about/index.html\"></a>"
+        );
+    }
+
+    #[test]
+    fn index_file_works() {
+        assert_eq!(index_file(""), "index.html");
+        assert_eq!(index_file("about"), "about/index.html");
+        assert_eq!(index_file("blog/post/"), "blog/post/index.html");
+        assert_eq!(index_file("about#team"), "about/index.html#team");
+        assert_eq!(index_file("?page=2"), "index.html?page=2");
+        assert_eq!(index_file("404.html"), "404.html");
+        assert_eq!(index_file("public/image.png#x"), "public/image.png#x");
+    }
+
+    #[test]
+    fn routes_using_templates_works() {
+        let mut templates = FileMap::new();
+        templates.insert("page".to_string(), "{{> boilerplate}}".to_string());
+        templates.insert("boilerplate".to_string(), "{{> CSS}}".to_string());
+        templates.insert("other/article".to_string(), "hi".to_string());
+
+        let mut routes = RouteMap::new();
+        let template = |name: &str| Page::Template {
+            template: name.to_string(),
+            data: Object::new(),
+        };
+        routes.insert("".to_string(), template("page"));
+        routes.insert("article".to_string(), template("other/article"));
+        routes.insert("raw".to_string(), Page::Raw("raw".to_string()));
+
+        let changed = vec!["boilerplate".to_string()];
+        assert_eq!(
+            routes_using_templates(&routes, &templates, &changed),
+            Some(vec!["".to_string()])
+        );
+
+        let changed = vec!["unused".to_string()];
+        assert_eq!(
+            routes_using_templates(&routes, &templates, &changed),
+            Some(vec![])
+        );
+
+        templates.insert("boilerplate".to_string(), "{{> (name)}}".to_string());
+        assert_eq!(routes_using_templates(&routes, &templates, &changed), None);
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{files::normalize_path, Config, Error, OutputMap};
+
+/// Attributes which contain links, checked for root-relative paths
+const LINK_ATTRIBUTES: &[&str] = &["href", "src", "action"];
+/// Attributes which contain links to check for broken internal links
+const CHECKED_ATTRIBUTES: &[&str] = &["href", "src"];
+/// Elements whose content is not scanned for links, as it is code or text (such as example markup), not page markup
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "pre", "code", "textarea"];
+
+/// An attribute with a value, in an HTML tag
+struct Attribute<'a> {
+    /// Name of element, such as `a`
+    tag: &'a str,
+    /// Name of attribute, such as `href`
+    name: &'a str,
+    /// Value of attribute, without quotes
+    value: &'a str,
+    /// Byte index of start of value in page
+    start: usize,
+}
+
+/// Prepend base path to root-relative links in a page, such as `href="/about"`
+///
+/// Links which already start with the base path, protocol-relative links (`//example.com`), and other links are unchanged.
+/// Only attributes of tags are changed, not text, comments, or the content of elements such as `script` and `code`.
+/// If `rewrite` is `false`, the first root-relative link which does not start with the base path is an error.
+/// Nothing is changed if base path is empty
+///
+/// - `page`: Name of route, for error message
+pub fn base_path_links(
+    html: String,
+    page: &str,
+    base_path: &str,
+    rewrite: bool,
+) -> Result<String, Error> {
+    if base_path.is_empty() {
+        return Ok(html);
+    }
+
+    let mut output = String::new();
+    let mut copied = 0;
+
+    for attribute in attributes(&html) {
+        if !is_attribute(&attribute, LINK_ATTRIBUTES)
+            || !is_root_relative(attribute.value)
+            || is_under_base(attribute.value, base_path)
+        {
+            continue;
+        }
+        if !rewrite {
+            return fail!(
+                RootRelativeLink,
+                page.to_string(),
+                attribute.value.to_string()
+            );
+        }
+
+        output += &html[copied..attribute.start];
+        output += "/";
+        output += base_path;
+        copied = attribute.start;
+    }
+
+    output += &html[copied..];
+    Ok(output)
+}
+
+/// Whether an attribute has one of the names
+fn is_attribute(attribute: &Attribute, names: &[&str]) -> bool {
+    names
+        .iter()
+        .any(|name| attribute.name.eq_ignore_ascii_case(name))
+}
+
+/// Whether an attribute can be the target of a `#fragment` link
+///
+/// Any element with an `id`, or an `a` element with a `name` (not form fields such as `<input name="email">`)
+fn is_anchor(attribute: &Attribute) -> bool {
+    attribute.name.eq_ignore_ascii_case("id")
+        || (attribute.name.eq_ignore_ascii_case("name") && attribute.tag.eq_ignore_ascii_case("a"))
+}
+
+/// Get every attribute with a value, in the tags of a page
+///
+/// Text, comments, closing tags, and the content of skipped elements (see [`SKIPPED_ELEMENTS`]) are not included
+fn attributes(html: &str) -> Vec<Attribute<'_>> {
+    // Same byte indexes as `html`
+    let lower = html.to_ascii_lowercase();
+    let bytes = html.as_bytes();
+    let is_space = |index: usize| bytes[index].is_ascii_whitespace();
+
+    let mut attributes = Vec::new();
+    let mut i = 0;
+
+    while let Some(start) = find_from(html, "<", i) {
+        // Comment
+        if lower[start..].starts_with("<!--") {
+            i = find_from(html, "-->", start + 4).map_or(html.len(), |end| end + 3);
+            continue;
+        }
+        // Closing tag, doctype, or processing instruction
+        if matches!(bytes.get(start + 1), Some(b'/' | b'!' | b'?')) {
+            i = find_from(html, ">", start).map_or(html.len(), |end| end + 1);
+            continue;
+        }
+        // Text, such as `a < b`
+        if !bytes.get(start + 1).is_some_and(u8::is_ascii_alphabetic) {
+            i = start + 1;
+            continue;
+        }
+
+        // Name of element
+        let mut j = start + 1;
+        while j < html.len() && !is_space(j) && !matches!(bytes[j], b'/' | b'>') {
+            j += 1;
+        }
+        let tag = &html[start + 1..j];
+
+        // Attributes, until end of tag
+        loop {
+            while j < html.len() && (is_space(j) || bytes[j] == b'/') {
+                j += 1;
+            }
+            if j >= html.len() {
+                break;
+            }
+            if bytes[j] == b'>' {
+                j += 1;
+                break;
+            }
+
+            // Name of attribute
+            let name_start = j;
+            j += 1;
+            while j < html.len() && !is_space(j) && !matches!(bytes[j], b'=' | b'/' | b'>') {
+                j += 1;
+            }
+            let name = &html[name_start..j];
+
+            // Attribute without value
+            while j < html.len() && is_space(j) {
+                j += 1;
+            }
+            if bytes.get(j) != Some(&b'=') {
+                continue;
+            }
+            j += 1;
+            while j < html.len() && is_space(j) {
+                j += 1;
+            }
+
+            // Quoted or unquoted value
+            let (value_start, value_end) = match bytes.get(j) {
+                Some(quote @ (b'"' | b'\'')) => {
+                    let end =
+                        find_from(html, &(*quote as char).to_string(), j + 1).unwrap_or(html.len());
+                    let value = (j + 1, end);
+                    j = (end + 1).min(html.len());
+                    value
+                }
+                _ => {
+                    let value_start = j;
+                    while j < html.len() && !is_space(j) && bytes[j] != b'>' {
+                        j += 1;
+                    }
+                    (value_start, j)
+                }
+            };
+            attributes.push(Attribute {
+                tag,
+                name,
+                value: &html[value_start..value_end],
+                start: value_start,
+            });
+        }
+        i = j;
+
+        // Skip content of element, until closing tag
+        let tag = tag.to_ascii_lowercase();
+        if SKIPPED_ELEMENTS.contains(&tag.as_str()) {
+            i = find_from(&lower, &format!("</{tag}"), i).unwrap_or(html.len());
+        }
+    }
+
+    attributes
+}
+
+/// Find a string in html, starting from a byte index
+fn find_from(html: &str, pattern: &str, from: usize) -> Option<usize> {
+    html.get(from..)?.find(pattern).map(|index| index + from)
+}
+
+/// Check internal links (`href` and `src` attributes) of every HTML file in outputs
+///
+/// Links are resolved against output files, the same as the dev server: `about` can be `about`, `about.html`, or `about/index.html`.
+/// Relative links are resolved from the folder of the page, and links starting with the app url are resolved from the root of the site.
+/// Links to `#fragment` anchors must match an `id` attribute, or the `name` of an `a` element, in the target page.
+/// Only attributes of tags are checked, not text, comments, or the content of elements such as `script` and `code`
+///
+/// External links (such as `https://other.com` or `mailto:`) are not checked.
+/// In *dev mode*, links to public files are not checked, as public files are not copied
+///
+/// - `copied`: Other files in build directory, which are copied without being read (such as public files), by path relative to build directory.
+///   Anchors in these files are not checked
+///
+/// Returns a message for each broken link
+pub fn check_links(
+    outputs: &OutputMap,
+    copied: &HashSet<String>,
+    url: &str,
+    config: &Config,
+    is_dev: bool,
+) -> Vec<String> {
+    let mut broken = Vec::new();
+    // Anchors of each page, read when first linked to
+    let mut anchors: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+    for (page, content) in outputs {
+        if !page.ends_with(".html") {
+            continue;
+        }
+        let Ok(html) = std::str::from_utf8(content) else {
+            continue;
+        };
+        // Folder of page, for relative links
+        let folder = page.rsplit_once('/').map_or("", |(folder, _)| folder);
+
+        for link in attributes(html)
+            .into_iter()
+            .filter(|attribute| is_attribute(attribute, CHECKED_ATTRIBUTES))
+            .map(|attribute| attribute.value)
+        {
+            let Some(path) = resolve_link(link, folder, url, &config.base_path) else {
+                continue;
+            };
+            let (path, fragment) = match path.split_once('#') {
+                Some((path, fragment)) => (path.to_string(), Some(fragment)),
+                None => (path, None),
+            };
+
+            if is_dev && path.starts_with(&format!("{}/", config.build_public)) {
+                continue;
+            }
+
+            // Empty path (such as `#top` or `?page=2`) links to same page
+            let target = if link.starts_with(['#', '?']) {
+                Some(page.as_str())
+            } else if path.starts_with("..") {
+                None
+            } else {
+                find_output(outputs, copied, &path)
+            };
+            let Some(target) = target else {
+                broken.push(format!("Broken link '{link}' in '{page}'"));
+                continue;
+            };
+
+            // Check anchor exists in target page
+            let Some(fragment) =
+                fragment.filter(|fragment| !fragment.is_empty() && *fragment != "top")
+            else {
+                continue;
+            };
+            let Some(content) = outputs.get(target) else {
+                continue;
+            };
+            let fragment = decode_percent(fragment);
+            let ids = anchors.entry(target).or_insert_with(|| {
+                std::str::from_utf8(content)
+                    .map(|html| {
+                        attributes(html)
+                            .into_iter()
+                            .filter(is_anchor)
+                            .map(|attribute| attribute.value)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            });
+            if !ids.contains(fragment.as_str()) {
+                broken.push(format!(
+                    "Broken anchor '#{fragment}' of link '{link}' in '{page}'"
+                ));
+            }
+        }
+    }
+
+    broken
+}
+
+/// Resolve an internal link to a path relative to build directory, including any `#fragment`
+///
+/// Returns `None` for external links, or `Some` path starting with `..` for links outside the site
+fn resolve_link(link: &str, folder: &str, url: &str, base_path: &str) -> Option<String> {
+    let link = link.trim();
+
+    // Query is not used to find file
+    let (path, fragment) = match link.split_once('#') {
+        Some((path, fragment)) => (path, format!("#{fragment}")),
+        None => (link, String::new()),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+
+    let app_url = path
+        .strip_prefix(url)
+        .or_else(|| (path == url.trim_end_matches('/')).then_some(""));
+
+    let path = if let Some(path) = app_url {
+        // Absolute url of app (such as `URL` helper)
+        path.to_string()
+    } else if path.starts_with("//") || is_external(path) {
+        return None;
+    } else if let Some(path) = path.strip_prefix('/') {
+        // Root-relative, under base path
+        match base_path {
+            "" => path.to_string(),
+            _ => match path.strip_prefix(base_path) {
+                Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.to_string(),
+                _ => format!("../{path}"),
+            },
+        }
+    } else {
+        format!("{folder}/{path}")
+    };
+
+    Some(normalize_path(&decode_percent(&path)) + &fragment)
+}
+
+/// Whether a link starts with a scheme, such as `https:` or `mailto:`
+fn is_external(link: &str) -> bool {
+    match link.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Find output file for a path, the same as the dev server
+///
+/// Returns path of output file, or copied file
+fn find_output<'a>(
+    outputs: &'a OutputMap,
+    copied: &'a HashSet<String>,
+    path: &str,
+) -> Option<&'a str> {
+    let candidates = if path.is_empty() {
+        vec!["index.html".to_string()]
+    } else {
+        vec![
+            path.to_string(),
+            format!("{path}.html"),
+            format!("{path}/index.html"),
+        ]
+    };
+    candidates
+        .iter()
+        .find_map(|path| {
+            outputs
+                .get_key_value(path)
+                .map(|(path, _)| path)
+                .or_else(|| copied.get(path))
+        })
+        .map(String::as_str)
+}
+
+/// Decode percent-encoded characters in a link, such as `%20`
+///
+/// Invalid sequences are unchanged
+fn decode_percent(link: &str) -> String {
+    let bytes = link.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Whether a link starts with a single slash
+fn is_root_relative(link: &str) -> bool {
+    link.starts_with('/') && !link.starts_with("//")
+}
+
+/// Whether a root-relative link starts with the base path
+fn is_under_base(link: &str, base_path: &str) -> bool {
+    match link
+        .strip_prefix('/')
+        .and_then(|link| link.strip_prefix(base_path))
+    {
+        Some(rest) => rest.is_empty() || rest.starts_with(['/', '?', '#']),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_path_links_works() {
+        let html = "<a href=\"/about\">About</a><img src=/public/icon.png>\
+            <a href='/project/'>Home</a><a href=\"//cdn.example.com/a.js\"></a>\
+            <a data-src=\"/raw\" href=\"https://example.com/\"></a><form action=\"/projects\"></form>";
+
+        assert_eq!(
+            base_path_links(html.to_string(), "page", "project", true).unwrap(),
+            "<a href=\"/project/about\">About</a><img src=/project/public/icon.png>\
+            <a href='/project/'>Home</a><a href=\"//cdn.example.com/a.js\"></a>\
+            <a data-src=\"/raw\" href=\"https://example.com/\"></a><form action=\"/project/projects\"></form>"
+        );
+
+        assert!(matches!(
+            base_path_links(html.to_string(), "page", "project", false),
+            Err(Error::RootRelativeLink(page, link)) if page == "page" && link == "/about",
+        ));
+
+        assert_eq!(
+            base_path_links(html.to_string(), "page", "", false).unwrap(),
+            html
+        );
+    }
+
+    #[test]
+    fn base_path_links_skips_text() {
+        let skipped = [
+            "<script>const link = '<a href=\"/about\">';</script>",
+            "<style>a[href=\"/about\"] { color: red; }</style>",
+            "<pre>&lt;a href=/about&gt; <a href=\"/about\"></a></pre>",
+            "<code class=\"html\">&lt;img src=\"/icon.png\"&gt;</code>",
+            "<textarea name=\"body\">action=/submit</textarea>",
+            "<!-- <a href=\"/about\"> -->",
+            "<p>Set href=\"/about\" on links</p>",
+        ];
+        for html in skipped {
+            assert_eq!(
+                base_path_links(html.to_string(), "page", "project", false).unwrap(),
+                html
+            );
+        }
+
+        // Tags after skipped elements are still changed
+        assert_eq!(
+            base_path_links(
+                "<SCRIPT>a < b</Script><p>1 < 2</p><a href=/about>".to_string(),
+                "page",
+                "project",
+                true
+            )
+            .unwrap(),
+            "<SCRIPT>a < b</Script><p>1 < 2</p><a href=/project/about>"
+        );
+    }
+
+    #[test]
+    fn check_links_works() {
+        let mut outputs = OutputMap::new();
+        let mut add = |path: &str, content: &str| {
+            outputs.insert(path.to_string(), content.as_bytes().to_vec());
+        };
+        add(
+            "index.html",
+            "<a href=\"/about\">About</a><a href='about#team'></a><a href=https://example.com/blog/post>\
+            <link href=\"https://example.com/styles/global/style.css\"><img src=\"/public/my%20icon.png\">\
+            <a href=\"https://other.com/missing\"></a><a href=\"mailto:me@example.com\"></a><a href=\"#top\"></a>",
+        );
+        add(
+            "about/index.html",
+            "<h2 id=\"team\">Team</h2><a href=\"../\"></a><a href=\"./#team\"></a><a href=\"#people\"></a>\
+            <a href=\"../missing\"></a><a href=\"/about#contact\"></a><a href=\"../../outside\"></a>",
+        );
+        add(
+            "blog/post.html",
+            "<a data-href=\"/hidden\"></a><a href=\"?page=2\"></a>",
+        );
+        // Only `a` elements are anchors by name, and example markup is not checked
+        add(
+            "contact.html",
+            "<input name=\"email\"><a name=\"form\"></a><a href=\"#email\"></a><a href=\"#form\"></a>\
+            <code>&lt;a href=\"/missing\"&gt;</code><pre><a href=\"/missing\"></a></pre>\
+            <!-- <a href=\"/missing\"> --><script>'<a href=\"/missing\">'</script><p>Use href=\"/missing\"</p>",
+        );
+        add("styles/global/style.css", "a{color:red}");
+        // Public files are copied without being read
+        let copied = HashSet::from(["public/my icon.png".to_string()]);
+
+        let config = Config::default();
+        assert_eq!(
+            check_links(&outputs, &copied, "https://example.com/", &config, false),
+            [
+                "Broken anchor '#people' of link '#people' in 'about/index.html'",
+                "Broken link '../missing' in 'about/index.html'",
+                "Broken anchor '#contact' of link '/about#contact' in 'about/index.html'",
+                "Broken link '../../outside' in 'about/index.html'",
+                "Broken anchor '#email' of link '#email' in 'contact.html'",
+            ]
+        );
+
+        // Links outside base path
+        let config = Config {
+            base_path: "project".to_string(),
+            ..Config::default()
+        };
+        let mut outputs = OutputMap::new();
+        outputs.insert(
+            "index.html".to_string(),
+            b"<a href=\"/project/\"></a><a href=\"/project\"></a><a href=\"/other\"></a>".to_vec(),
+        );
+        assert_eq!(
+            check_links(
+                &outputs,
+                &HashSet::new(),
+                "https://example.com/project/",
+                &config,
+                false
+            ),
+            ["Broken link '/other' in 'index.html'"]
+        );
+    }
+}
@@ -1,7 +1,29 @@
+/// Gzip and brotli compression of output files
+#[cfg(feature = "compress")]
+mod compress;
+/// Inlining critical CSS into pages
+mod critical;
+/// Post-processing of compiled CSS
+mod css;
 /// Specifically for HBS->HTML template rendering
 mod hbs;
+/// Checking and rewriting links in rendered pages
+mod links;
+/// Removing unused CSS rules
+mod purge;
 /// Specifically for SCSS->CSS conversion
 mod scss;
+/// Source maps for compiled CSS
+mod sourcemap;
 
-pub(crate) use hbs::{register_inbuilt, register_templates, render_page};
-pub(crate) use scss::scss_to_css;
+#[cfg(feature = "compress")]
+pub(crate) use compress::{compress_file, compress_outputs, is_compressible};
+pub(crate) use critical::{inline_critical_css, strip_critical_markers};
+pub(crate) use css::{parse_targets, process_css};
+#[cfg(feature = "watch")]
+pub(crate) use hbs::routes_using_templates;
+pub(crate) use hbs::{register_inbuilt, register_templates, render_page, style_path};
+pub(crate) use links::{base_path_links, check_links};
+pub(crate) use purge::{purge_css, UsedSelectors};
+pub(crate) use scss::{is_partial, load_paths, scss_to_css, style_syntax};
+pub(crate) use sourcemap::source_map;
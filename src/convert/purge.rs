@@ -0,0 +1,459 @@
+use std::collections::HashSet;
+
+/// Names of classes, ids, and tags used in HTML pages
+#[derive(Debug, Default)]
+pub struct UsedSelectors {
+    classes: HashSet<String>,
+    ids: HashSet<String>,
+    tags: HashSet<String>,
+}
+
+impl UsedSelectors {
+    /// Add the classes, ids, and tags of every element in a HTML page
+    ///
+    /// Elements inside comments are skipped
+    pub fn scan(&mut self, html: &str) {
+        let mut rest = html;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start + 1..];
+
+            // Skip comment
+            if let Some(comment) = rest.strip_prefix("!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+
+            // Tag name, skipping closing tags and doctype
+            let name_end = rest
+                .find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '-')
+                .unwrap_or(rest.len());
+            if name_end == 0 {
+                continue;
+            }
+            self.tags.insert(rest[..name_end].to_lowercase());
+            rest = &rest[name_end..];
+
+            // Attributes, until end of tag
+            while let Some((name, value, remaining)) = next_attribute(rest) {
+                rest = remaining;
+                match name.to_lowercase().as_str() {
+                    "class" => self
+                        .classes
+                        .extend(value.split_whitespace().map(str::to_string)),
+                    "id" => {
+                        self.ids.insert(value.trim().to_string());
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+/// Parse the next attribute of a HTML tag, as name and value (empty if no value given)
+///
+/// Returns `None` at the end of the tag
+fn next_attribute(tag: &str) -> Option<(&str, &str, &str)> {
+    let tag = tag.trim_start_matches(|ch: char| ch.is_whitespace() || ch == '/');
+    if tag.is_empty() || tag.starts_with('>') {
+        return None;
+    }
+
+    // Attribute name
+    let name_end = tag
+        .find(|ch: char| ch.is_whitespace() || matches!(ch, '=' | '>' | '/'))
+        .unwrap_or(tag.len())
+        // Always move forward, for malformed tags such as `<a =>`
+        .max(1);
+    let (name, rest) = tag.split_at(name_end);
+
+    // No value
+    let Some(rest) = rest.trim_start().strip_prefix('=') else {
+        return Some((name, "", rest));
+    };
+    let rest = rest.trim_start();
+
+    // Quoted or unquoted value
+    let (value, rest) = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let rest = &rest[1..];
+            match rest.find(quote) {
+                Some(end) => (&rest[..end], &rest[end + 1..]),
+                None => (rest, ""),
+            }
+        }
+        _ => {
+            let end = rest
+                .find(|ch: char| ch.is_whitespace() || ch == '>')
+                .unwrap_or(rest.len());
+            rest.split_at(end)
+        }
+    };
+
+    Some((name, value, rest))
+}
+
+/// Remove rules from CSS with selectors that do not match any used classes, ids, or tags
+///
+/// Selectors in a list are removed individually. Rules inside `@media`, `@supports`, `@layer`, and `@container` blocks are also purged.
+/// Other at-rules (such as `@font-face` and `@keyframes`) are always kept
+///
+/// Classes, ids, and tags matching a pattern in the safelist are always kept. Patterns may use `*` to match any characters
+pub fn purge_css(css: &str, used: &UsedSelectors, safelist: &[String]) -> String {
+    let mut output = String::new();
+    let mut rest = css;
+
+    loop {
+        // Keep whitespace and comments before rule
+        let start = skip_space(rest);
+        output += &rest[..start];
+        rest = &rest[start..];
+
+        let Some((end, ch)) = find_unquoted(rest, &['{', ';', '}']) else {
+            output += rest;
+            break;
+        };
+
+        // Statement, such as `@import`, or unexpected closing brace
+        if ch != '{' {
+            output += &rest[..=end];
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        let prelude = &rest[..end];
+        let block_end = end + matching_brace(&rest[end..]);
+        let block = &rest[end + 1..block_end.min(rest.len())];
+        let rule = &rest[..(block_end + 1).min(rest.len())];
+        rest = &rest[rule.len()..];
+
+        // Purge rules inside nested at-rules, removing block if empty
+        if let Some(at_rule) = prelude.trim_start().strip_prefix('@') {
+            let name = at_rule
+                .split(|ch: char| !ch.is_alphanumeric() && ch != '-')
+                .next()
+                .unwrap_or("");
+            if matches!(name, "media" | "supports" | "layer" | "container") {
+                let purged = purge_css(block, used, safelist);
+                if !purged.trim().is_empty() {
+                    output += &format!("{prelude}{{{purged}}}");
+                } else {
+                    rest = rest.trim_start();
+                }
+            } else {
+                output += rule;
+            }
+            continue;
+        }
+
+        // Style rule, with unused selectors removed
+        let selectors = split_selectors(prelude);
+        let kept: Vec<_> = selectors
+            .iter()
+            .filter(|selector| selector_is_used(selector, used, safelist))
+            .collect();
+
+        if kept.len() == selectors.len() {
+            output += rule;
+        } else if !kept.is_empty() {
+            let kept: Vec<_> = kept.iter().map(|selector| selector.trim()).collect();
+            output += &format!("{} {{{block}}}", kept.join(", "));
+        } else {
+            // Skip whitespace after removed rule
+            rest = rest.trim_start();
+        }
+    }
+
+    output
+}
+
+/// Check if a selector may match any element, using the classes, ids, and tags used
+///
+/// Selectors inside pseudo-classes (such as `:not(.a)`) and attribute selectors are not checked
+fn selector_is_used(selector: &str, used: &UsedSelectors, safelist: &[String]) -> bool {
+    let is_used = |names: &HashSet<String>, name: &str| {
+        names.contains(name)
+            || safelist
+                .iter()
+                .any(|pattern| matches_pattern(pattern, name))
+    };
+
+    let mut chars = selector.chars().peekable();
+    // Whether the next character starts a compound selector
+    let mut compound_start = true;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            // Class or id
+            '.' | '#' => {
+                let names = if ch == '.' { &used.classes } else { &used.ids };
+                let name = read_ident(&mut chars);
+                if !is_used(names, &name) {
+                    return false;
+                }
+            }
+
+            // Pseudo-class or pseudo-element, with optional arguments
+            ':' => {
+                if chars.peek() == Some(&':') {
+                    chars.next();
+                }
+                read_ident(&mut chars);
+                if chars.peek() == Some(&'(') {
+                    skip_group(&mut chars, '(', ')');
+                }
+            }
+            '[' => skip_group(&mut chars, '[', ']'),
+
+            ch if ch.is_whitespace() || matches!(ch, '>' | '+' | '~' | '*' | '|' | '&') => {
+                compound_start = true;
+                continue;
+            }
+
+            // Tag name
+            _ if compound_start => {
+                let tag = format!("{ch}{}", read_ident(&mut chars)).to_lowercase();
+                if !is_used(&used.tags, &tag) {
+                    return false;
+                }
+            }
+
+            _ => (),
+        }
+        compound_start = false;
+    }
+
+    true
+}
+
+/// Read an identifier, such as a class name, unescaping characters
+fn read_ident(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut ident = String::new();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            '\\' => {
+                chars.next();
+                if let Some(ch) = chars.next() {
+                    ident.push(ch);
+                }
+            }
+            ch if ch.is_alphanumeric() || matches!(ch, '-' | '_') || !ch.is_ascii() => {
+                chars.next();
+                ident.push(ch);
+            }
+            _ => break,
+        }
+    }
+    ident
+}
+
+/// Skip characters until the matching closing character, including nested groups
+fn skip_group(chars: &mut std::iter::Peekable<std::str::Chars>, open: char, close: char) {
+    let mut depth = 0;
+    for ch in chars.by_ref() {
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            depth -= 1;
+            if depth <= 0 {
+                break;
+            }
+        }
+    }
+}
+
+/// Split a selector list by commas, ignoring commas inside parentheses or brackets
+fn split_selectors(selectors: &str) -> Vec<&str> {
+    let mut list = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, ch) in selectors.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                list.push(&selectors[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    list.push(&selectors[start..]);
+    list
+}
+
+/// Check if a name matches a pattern, where `*` matches any characters
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == name;
+    };
+    let Some(name) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    // Try every position for the rest of the pattern
+    (0..=name.len())
+        .filter(|i| name.is_char_boundary(*i))
+        .any(|i| matches_pattern(rest, &name[i..]))
+}
+
+/// Get the length of leading whitespace and comments
+fn skip_space(css: &str) -> usize {
+    let mut rest = css;
+    loop {
+        let trimmed = rest.trim_start();
+        match trimmed.strip_prefix("/*") {
+            Some(comment) => rest = comment.find("*/").map_or("", |end| &comment[end + 2..]),
+            None => return css.len() - trimmed.len(),
+        }
+    }
+}
+
+/// Find the first of some characters, skipping strings and comments
+fn find_unquoted(css: &str, targets: &[char]) -> Option<(usize, char)> {
+    let mut chars = css.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            _ if targets.contains(&ch) => return Some((i, ch)),
+            '"' | '\'' => {
+                let mut escaped = false;
+                for (_, next) in chars.by_ref() {
+                    if next == ch && !escaped {
+                        break;
+                    }
+                    escaped = next == '\\' && !escaped;
+                }
+            }
+            '/' if chars.peek().map(|(_, ch)| *ch) == Some('*') => {
+                let mut previous = ' ';
+                for (_, next) in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Get the index of the closing brace, matching the opening brace at the start of the string
+///
+/// Returns the length of the string if the block is not closed
+fn matching_brace(css: &str) -> usize {
+    let mut depth = 0;
+    let mut offset = 0;
+    while let Some((i, ch)) = find_unquoted(&css[offset..], &['{', '}']) {
+        offset += i;
+        if ch == '{' {
+            depth += 1;
+        } else {
+            depth -= 1;
+            if depth == 0 {
+                return offset;
+            }
+        }
+        offset += 1;
+    }
+    css.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn used(html: &str) -> UsedSelectors {
+        let mut used = UsedSelectors::default();
+        used.scan(html);
+        used
+    }
+
+    #[test]
+    fn scan_works() {
+        let used = used(
+            "<!DOCTYPE html><HTML><body class=\"a  b\"><p id=intro class=c>x</p>\
+            <!-- <span class=\"hidden\"> --><img src='a.png' alt=\"<b>\"/></body>",
+        );
+
+        let sorted = |set: &HashSet<String>| {
+            let mut list: Vec<_> = set.iter().cloned().collect();
+            list.sort();
+            list
+        };
+        assert_eq!(sorted(&used.classes), ["a", "b", "c"]);
+        assert_eq!(sorted(&used.ids), ["intro"]);
+        assert_eq!(sorted(&used.tags), ["body", "html", "img", "p"]);
+    }
+
+    #[test]
+    fn purge_css_works() {
+        let used = used("<body><p class=\"a md:flex\" id=\"x\"></p></body>");
+        let safelist = ["js-*".to_string()];
+
+        let css = "\
+body {
+  color: white;
+}
+.b, p.a {
+  color: red;
+}
+span {
+  color: blue;
+}
+.md\\:flex:hover, #x::before, .js-open {
+  content: \"}\";
+}
+p:not(.b) a {
+  color: green;
+}
+@media (min-width: 10px) {
+  .b {
+    color: red;
+  }
+}
+@media print {
+  #x {
+    color: black;
+  }
+}
+@font-face {
+  font-family: x;
+}
+";
+
+        assert_eq!(
+            purge_css(css, &used, &safelist),
+            "\
+body {
+  color: white;
+}
+p.a {
+  color: red;
+}
+.md\\:flex:hover, #x::before, .js-open {
+  content: \"}\";
+}
+@media print {
+  #x {
+    color: black;
+  }
+}
+@font-face {
+  font-family: x;
+}
+"
+        );
+    }
+
+    #[test]
+    fn matches_pattern_works() {
+        assert!(matches_pattern("active", "active"));
+        assert!(matches_pattern("js-*", "js-open"));
+        assert!(matches_pattern("*-open", "js-open"));
+        assert!(matches_pattern("a*b*c", "a-b-c"));
+        assert!(!matches_pattern("js-*", "open"));
+        assert!(!matches_pattern("active", "inactive"));
+    }
+}
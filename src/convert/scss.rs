@@ -1,25 +1,67 @@
-use css_minify::optimizations as css_minify;
+use grass::InputSyntax;
 
-use crate::Error;
+use crate::{files::get_extension, Config, Error, Source};
+
+/// Convert SCSS or Sass (indented syntax) file to CSS
+///
+/// Plain CSS is returned unchanged
+///
+/// `@use`, `@forward`, and `@import` rules are resolved with the load paths, in order, reading from the source files
+pub fn scss_to_css(
+    name: &str,
+    scss: &str,
+    syntax: InputSyntax,
+    load_paths: &[String],
+    source: &Source,
+) -> Result<String, Error> {
+    if syntax == InputSyntax::Css {
+        return Ok(scss.to_string());
+    }
+
+    let options = grass::Options::default()
+        .load_paths(load_paths)
+        .input_syntax(syntax)
+        .fs(source);
 
-/// Convert SCSS file to CSS, and minify
-pub fn scss_to_css(name: &str, scss: &str, minify: bool) -> Result<String, Error> {
     // Convert scss to css
-    let css = try_unwrap!(
-        grass::from_string(scss, &Default::default()),
+    Ok(try_unwrap!(
+        grass::from_string(scss, &options),
         else Err(err) => return fail!(ScssConvert, name.to_string(), err),
-    );
+    ))
+}
 
-    // Minify
-    if minify {
-        return Ok(try_unwrap!(
-            css_minify::Minifier::default().minify(&css, css_minify::Level::Two),
-            else Err(err) => return fail!(CssMinify, name.to_string(), err.to_string()),
-        ));
+/// Get the syntax of a style file, from the file extension (`.scss`, `.sass`, or `.css`)
+///
+/// Returns `None` if the file is not a style file
+pub fn style_syntax(file: &str) -> Option<InputSyntax> {
+    match get_extension(file) {
+        "scss" => Some(InputSyntax::Scss),
+        "sass" => Some(InputSyntax::Sass),
+        "css" => Some(InputSyntax::Css),
+        _ => None,
     }
+}
 
-    // Don't minify
-    Ok(css)
+/// Check if a style file is a partial, which is only used by other styles, and not compiled alone
+///
+/// Partial file names start with an underscore, such as `_variables.scss`
+pub fn is_partial(name: &str) -> bool {
+    name.rsplit('/').next().unwrap_or(name).starts_with('_')
+}
+
+/// Get the load paths for a style file, to resolve `@use`, `@forward`, and `@import` rules
+///
+/// 1. Folder of the style file (for relative paths)
+/// 2. Styles source folder
+/// 3. Extra folders in `config.style_paths`
+pub fn load_paths(config: &Config, name: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    if let Some((parent, _)) = name.rsplit_once('/') {
+        paths.push(format!("{}/{}", config.styles, parent));
+    }
+    paths.push(config.styles.clone());
+    paths.extend(config.style_paths.iter().cloned());
+    paths
 }
 
 #[cfg(test)]
@@ -40,10 +82,11 @@ body {
 }
 ";
 
-        let css_large = scss_to_css("no_name", scss, false).expect("Should have parsed");
+        let css = scss_to_css("no_name", scss, InputSyntax::Scss, &[], &Source::Disk)
+            .expect("Should have parsed");
 
         assert_eq!(
-            css_large,
+            css,
             "\
 body {
   background-color: black;
@@ -53,9 +96,93 @@ body p {
 }
 "
         );
+    }
 
-        let css_mini = scss_to_css("no_name", scss, true).expect("Should have parsed");
+    #[test]
+    fn scss_modules_work() {
+        let config = Config {
+            styles: "tests/assets/styles".to_string(),
+            ..Config::default()
+        };
+        let name = "scoped/stylish";
+        let scss = include_str!("../../tests/assets/styles/scoped/stylish.scss");
 
-        assert_eq!(css_mini, "body{background:black}body p{color:white}");
+        assert_eq!(
+            load_paths(&config, name),
+            ["tests/assets/styles/scoped", "tests/assets/styles"]
+        );
+
+        let css = scss_to_css(
+            name,
+            scss,
+            InputSyntax::Scss,
+            &load_paths(&config, name),
+            &Source::Disk,
+        )
+        .expect("Should have parsed");
+        assert_eq!(
+            css,
+            "\
+p {
+  color: yellow;
+  background: #111;
+  border-color: #111;
+}
+"
+        );
+    }
+
+    #[test]
+    fn sass_and_css_work() {
+        let config = Config {
+            styles: "tests/assets/styles".to_string(),
+            ..Config::default()
+        };
+
+        let name = "scoped/indented";
+        let sass = include_str!("../../tests/assets/styles/scoped/indented.sass");
+        let css = scss_to_css(
+            name,
+            sass,
+            InputSyntax::Sass,
+            &load_paths(&config, name),
+            &Source::Disk,
+        )
+        .expect("Should have parsed");
+        assert_eq!(
+            css,
+            "\
+blockquote {
+  color: yellow;
+}
+blockquote cite {
+  font-style: italic;
+}
+"
+        );
+
+        let plain = include_str!("../../tests/assets/styles/plain.css");
+        let css = scss_to_css("plain", plain, InputSyntax::Css, &[], &Source::Disk)
+            .expect("Should have parsed");
+        assert_eq!(css, plain);
+    }
+
+    #[test]
+    fn style_syntax_works() {
+        assert_eq!(style_syntax("global.scss"), Some(InputSyntax::Scss));
+        assert_eq!(
+            style_syntax("scoped/indented.sass"),
+            Some(InputSyntax::Sass)
+        );
+        assert_eq!(style_syntax("theme.dark.css"), Some(InputSyntax::Css));
+        assert_eq!(style_syntax("notes.md"), None);
+    }
+
+    #[test]
+    fn is_partial_works() {
+        assert!(is_partial("_variables"));
+        assert!(is_partial("scoped/_colors"));
+        assert!(!is_partial("global"));
+        assert!(!is_partial("_scoped/stylish"));
     }
 }
@@ -0,0 +1,446 @@
+use serde_json::json;
+
+use crate::{files::relative_path, FileMap};
+
+/// Characters used for base64 VLQ encoding
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Create a source map (version 3) for a compiled stylesheet
+///
+/// The SCSS compiler does not track source positions, so mappings are only added if `approximate` is `true`.
+/// Otherwise the source map only includes the style file, without mappings.
+///
+/// Approximate mappings map each selector in the CSS to the source line with the same selector,
+/// and each declaration to the next source line with the same property.
+/// Sources are searched in the style file first, then any other style files (such as partials)
+///
+/// - `name`: Name of the style file with file extension, relative to styles source folder
+/// - `css`: Compiled CSS of the style file
+/// - `styles`: All style files with file extensions, including the style file
+/// - `folder`: Styles source folder
+/// - `output`: Path of the compiled stylesheet, relative to the current directory
+/// - `approximate`: Whether to add approximate mappings
+///
+/// Source paths are relative to the folder of the source map (next to `output`), as browsers resolve them from the map URL
+pub fn source_map(
+    name: &str,
+    css: &str,
+    styles: &FileMap,
+    folder: &str,
+    output: &str,
+    approximate: bool,
+) -> String {
+    let (output_folder, output_name) = output.rsplit_once('/').unwrap_or(("", output));
+
+    // Style file first, then other files in consistent order
+    let mut files: Vec<_> = styles.iter().filter(|(other, _)| *other != name).collect();
+    files.sort();
+    if let Some(entry) = styles.get_key_value(name) {
+        files.insert(0, entry);
+    }
+
+    // Lines of every source file
+    let lines: Vec<Vec<&str>> = files
+        .iter()
+        .map(|(_, scss)| scss.lines().collect())
+        .collect();
+
+    // Indexes of files which are used in mappings
+    let mut used_files = Vec::<usize>::new();
+
+    let mut mappings = String::new();
+    let mut encoder = Encoder::default();
+    // Source position of the last mapped selector
+    let mut cursor = (0, 0);
+
+    // Only style file, without mappings
+    let segments = if approximate {
+        css_segments(css)
+    } else {
+        if styles.contains_key(name) {
+            used_files.push(0);
+        }
+        Vec::new()
+    };
+
+    for segment in segments {
+        let position = match segment.kind {
+            SegmentKind::Selector => find_selector(segment.text, &lines, cursor),
+            SegmentKind::Declaration => find_declaration(segment.text, &lines, cursor),
+        };
+        let Some((file, line)) = position else {
+            continue;
+        };
+        if segment.kind == SegmentKind::Selector {
+            cursor = (file, line);
+        }
+
+        // Index of file in list of used files
+        let source = match used_files.iter().position(|used| *used == file) {
+            Some(index) => index,
+            None => {
+                used_files.push(file);
+                used_files.len() - 1
+            }
+        };
+
+        // Column of first non-whitespace character in source line
+        let column = lines[file][line].len() - lines[file][line].trim_start().len();
+
+        encoder.add(
+            &mut mappings,
+            segment.line,
+            segment.column,
+            source,
+            line,
+            column,
+        );
+    }
+
+    let sources: Vec<_> = used_files
+        .iter()
+        .map(|file| relative_path(output_folder, &format!("{}/{}", folder, files[*file].0)))
+        .collect();
+    let contents: Vec<_> = used_files.iter().map(|file| files[*file].1).collect();
+
+    json!({
+        "version": 3,
+        "file": output_name,
+        "sources": sources,
+        "sourcesContent": contents,
+        "names": [],
+        "mappings": mappings,
+    })
+    .to_string()
+}
+
+/// Find the source line of a selector
+///
+/// Matches the last compound selector (such as `a:hover` in `body a:hover`) on a line which opens a block.
+/// Lines after the cursor are checked first
+fn find_selector(
+    selector: &str,
+    lines: &[Vec<&str>],
+    cursor: (usize, usize),
+) -> Option<(usize, usize)> {
+    // Last selector in list, then last compound selector
+    let selector = selector.rsplit(',').next()?.trim();
+    let compound = selector.rsplit([' ', '>', '+', '~']).next()?.trim();
+    if compound.is_empty() {
+        return None;
+    }
+
+    find_line(lines, cursor, |line| {
+        line.contains('{') && line.split('{').next().unwrap_or("").contains(compound)
+    })
+}
+
+/// Find the source line of a declaration
+///
+/// Matches the property name at the start of a line, after the cursor
+fn find_declaration(
+    declaration: &str,
+    lines: &[Vec<&str>],
+    cursor: (usize, usize),
+) -> Option<(usize, usize)> {
+    let property = declaration.split(':').next()?.trim();
+    if property.is_empty() {
+        return None;
+    }
+
+    find_line(lines, cursor, |line| {
+        line.trim_start()
+            .strip_prefix(property)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    })
+}
+
+/// Find the first line (as file and line index) which matches a predicate
+///
+/// Lines after the cursor (in the same file) are checked first, then all files in order
+fn find_line<F>(lines: &[Vec<&str>], cursor: (usize, usize), predicate: F) -> Option<(usize, usize)>
+where
+    F: Fn(&str) -> bool,
+{
+    let (cursor_file, cursor_line) = cursor;
+
+    // After cursor
+    if let Some(file) = lines.get(cursor_file) {
+        for (line, text) in file.iter().enumerate().skip(cursor_line) {
+            if predicate(text) {
+                return Some((cursor_file, line));
+            }
+        }
+    }
+
+    // Any file
+    for (file, file_lines) in lines.iter().enumerate() {
+        for (line, text) in file_lines.iter().enumerate() {
+            if predicate(text) {
+                return Some((file, line));
+            }
+        }
+    }
+
+    None
+}
+
+/// Kind of CSS segment
+#[derive(Debug, PartialEq, Eq)]
+enum SegmentKind {
+    /// Selector or at-rule, before a block
+    Selector,
+    /// Declaration or statement, inside a block
+    Declaration,
+}
+
+/// A selector or declaration in CSS, with position of first character
+#[derive(Debug, PartialEq, Eq)]
+struct Segment<'a> {
+    kind: SegmentKind,
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+/// Split CSS into selectors and declarations
+///
+/// Comments and strings are skipped
+fn css_segments(css: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+
+    // Start of current segment (byte index, line, column)
+    let mut start: Option<(usize, usize, usize)> = None;
+    let (mut line, mut column) = (0, 0);
+
+    let mut chars = css.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            // End of segment
+            '{' | ';' | '}' => {
+                if let Some((index, line, column)) = start.take() {
+                    let kind = if ch == '{' {
+                        SegmentKind::Selector
+                    } else {
+                        SegmentKind::Declaration
+                    };
+                    segments.push(Segment {
+                        kind,
+                        text: css[index..i].trim(),
+                        line,
+                        column,
+                    });
+                }
+            }
+
+            // Skip comment
+            '/' if chars.peek().map(|(_, ch)| *ch) == Some('*') => {
+                let mut previous = ' ';
+                for (_, ch) in chars.by_ref() {
+                    advance(ch, &mut line, &mut column);
+                    if previous == '*' && ch == '/' {
+                        break;
+                    }
+                    previous = ch;
+                }
+            }
+
+            _ => {
+                if start.is_none() && !ch.is_whitespace() {
+                    start = Some((i, line, column));
+                }
+
+                // Skip string
+                if ch == '"' || ch == '\'' {
+                    let mut escaped = false;
+                    advance(ch, &mut line, &mut column);
+                    for (_, next) in chars.by_ref() {
+                        advance(next, &mut line, &mut column);
+                        if next == ch && !escaped {
+                            break;
+                        }
+                        escaped = next == '\\' && !escaped;
+                    }
+                    continue;
+                }
+            }
+        }
+
+        advance(ch, &mut line, &mut column);
+    }
+
+    segments
+}
+
+/// Advance line and column position by a character
+///
+/// Columns are counted in UTF-16 units, as required by source maps
+fn advance(ch: char, line: &mut usize, column: &mut usize) {
+    if ch == '\n' {
+        *line += 1;
+        *column = 0;
+    } else {
+        *column += ch.len_utf16();
+    }
+}
+
+/// Encode source map mappings, relative to previous mapping
+#[derive(Default)]
+struct Encoder {
+    /// Generated line of previous mapping
+    line: usize,
+    /// Generated column of previous mapping, in the same line
+    column: usize,
+    /// Source file, line, and column of previous mapping
+    source: (usize, usize, usize),
+    /// Whether any mapping has been added in the current line
+    has_mapping: bool,
+}
+
+impl Encoder {
+    /// Add a mapping to the output
+    ///
+    /// Mappings must be added in order of generated position
+    fn add(
+        &mut self,
+        out: &mut String,
+        line: usize,
+        column: usize,
+        source: usize,
+        source_line: usize,
+        source_column: usize,
+    ) {
+        // New lines
+        while self.line < line {
+            out.push(';');
+            self.line += 1;
+            self.column = 0;
+            self.has_mapping = false;
+        }
+        if self.has_mapping {
+            out.push(',');
+        }
+
+        encode_vlq(out, column as i64 - self.column as i64);
+        encode_vlq(out, source as i64 - self.source.0 as i64);
+        encode_vlq(out, source_line as i64 - self.source.1 as i64);
+        encode_vlq(out, source_column as i64 - self.source.2 as i64);
+
+        self.column = column;
+        self.source = (source, source_line, source_column);
+        self.has_mapping = true;
+    }
+}
+
+/// Encode an integer as a base64 VLQ
+fn encode_vlq(out: &mut String, value: i64) {
+    // Sign is stored in least significant bit
+    let mut value = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+
+    loop {
+        let mut digit = value & 0b11111;
+        value >>= 5;
+        if value > 0 {
+            // Continuation bit
+            digit |= 0b100000;
+        }
+        out.push(BASE64_CHARS[digit as usize] as char);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_vlq_works() {
+        let encode = |value| {
+            let mut out = String::new();
+            encode_vlq(&mut out, value);
+            out
+        };
+
+        assert_eq!(encode(0), "A");
+        assert_eq!(encode(1), "C");
+        assert_eq!(encode(-1), "D");
+        assert_eq!(encode(16), "gB");
+        assert_eq!(encode(-17), "jB");
+    }
+
+    #[test]
+    fn css_segments_works() {
+        let css = "body{color:red}\n/* a; {b} */\np a, .x::before {\n  content: \"; }\";\n}";
+        let segments = css_segments(css);
+        let summary: Vec<_> = segments
+            .iter()
+            .map(|segment| (segment.text, segment.line, segment.column))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("body", 0, 0),
+                ("color:red", 0, 5),
+                ("p a, .x::before", 2, 0),
+                ("content: \"; }\"", 3, 2),
+            ]
+        );
+        assert_eq!(segments[0].kind, SegmentKind::Selector);
+        assert_eq!(segments[1].kind, SegmentKind::Declaration);
+    }
+
+    #[test]
+    fn source_map_works() {
+        let mut styles = FileMap::new();
+        styles.insert(
+            "global.scss".to_string(),
+            "@use 'variables';\n\nbody {\n  color: white;\n\n  a {\n    color: variables.$link;\n  }\n}\n"
+                .to_string(),
+        );
+        styles.insert("_variables.scss".to_string(), "$link: red;\n".to_string());
+
+        let css = "body {\n  color: white;\n}\nbody a {\n  color: red;\n}\n";
+        let map: serde_json::Value = serde_json::from_str(&source_map(
+            "global.scss",
+            css,
+            &styles,
+            "assets/styles",
+            "build/styles/global/style.css",
+            true,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            map["sources"],
+            json!(["../../../assets/styles/global.scss"])
+        );
+        assert_eq!(map["file"], "style.css");
+        // body -> line 3; color -> line 4; body a -> line 6; color -> line 7
+        assert_eq!(map["mappings"], "AAEA;EACE;;AAEA;EACE");
+
+        // Not approximate, so no mappings
+        let map: serde_json::Value = serde_json::from_str(&source_map(
+            "global.scss",
+            css,
+            &styles,
+            "assets/styles",
+            "build/styles/global/style.css",
+            false,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            map["sources"],
+            json!(["../../../assets/styles/global.scss"])
+        );
+        assert_eq!(map["sourcesContent"][0], styles["global.scss"]);
+        assert_eq!(map["mappings"], "");
+    }
+}
@@ -17,6 +17,45 @@ pub enum Error {
     #[error("Failed to minify CSS file '{0}': {1}")]
     CssMinify(String, String),
 
+    #[error("Failed to process CSS file '{0}' for browser targets: {1}")]
+    CssProcess(String, String),
+
+    #[error("Invalid browser target '{0}'")]
+    InvalidTarget(String),
+
+    #[error("Style output path '{0}' must include `{{name}}`")]
+    InvalidStyleOutput(String),
+
+    #[error("Style files '{1}' and '{2}' have the same name '{0}', so would overwrite each other")]
+    DuplicateStyle(String, String, String),
+
+    #[error("Failed to parse config file '{0}': {1}")]
+    ParseConfig(String, Box<toml::de::Error>),
+
+    #[error("Invalid environment variable '{0}': {1}")]
+    ConfigEnv(String, String),
+
+    #[error("Invalid config value for '{0}': {1}")]
+    InvalidConfig(String, String),
+
+    #[error("Invalid command-line arguments: {0}. Run with `--help` for usage")]
+    InvalidArgs(String),
+
+    #[error("Root-relative link '{1}' in route '{0}' does not include base path. Use the `path` helper, or enable `rewrite_links` in config")]
+    RootRelativeLink(String, String),
+
+    #[error("Route does not exist '{0}'")]
+    RouteNotExist(String),
+
+    #[error("Found {} broken internal link(s):\n{}", .0.len(), .0.join("\n"))]
+    BrokenLinks(Vec<String>),
+
+    #[error("Invalid data for {0}: {1}")]
+    InvalidData(String, String),
+
+    #[error("Failed to compress file '{0}': {1}")]
+    Compress(String, io::Error),
+
     #[error("Failed to render Handlebars template '{0}': {1}")]
     RenderTemplate(String, Box<handlebars::RenderError>),
 
@@ -28,6 +67,9 @@ pub enum Error {
 
     #[error("Template does not exist with name '{0}' in templates directory")]
     TemplateNotExist(String),
+
+    #[error("Invalid ignore pattern '{0}': {1}")]
+    IgnorePattern(String, Box<ignore::Error>),
 }
 
 /// Error type for `Unreact`, relating to IO fails
@@ -45,9 +87,18 @@ pub enum IoError {
     #[error("Creating directory '{0}': {1}")]
     CreateDir(String, io::Error),
 
+    #[error("Renaming directory '{0}': {1}")]
+    RenameDir(String, io::Error),
+
+    #[error("Renaming directory '{0}': {1}. Previous build could not be restored, and was left at '{2}': {3}")]
+    RestoreDir(String, io::Error, String, io::Error),
+
     #[error("Copying directory '{0}': {1}")]
     CopyDir(String, io::Error),
 
+    #[error("Copying file '{0}': {1}")]
+    CopyFile(String, io::Error),
+
     #[error("Writing file '{0}': {1}")]
     WriteFile(String, io::Error),
 }
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::{Config, Error};
+
+/// Name of gitignore file, read from working directory
+const GITIGNORE_FILE: &str = ".gitignore";
+
+/// Filter for ignored source files, using `.gitignore`-style patterns
+///
+/// Patterns are from `.gitignore` in the working directory (if `config.gitignore` is `true`), then `config.ignore`.
+/// Later patterns take precedence, so `config.ignore` can un-ignore files with `!`
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    matcher: Gitignore,
+}
+
+impl FileFilter {
+    /// Create a new filter from config patterns
+    ///
+    /// Returns `Err` if a pattern is invalid, or `.gitignore` file could not be read
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        // Paths are relative to working directory
+        // Canonicalized, to match absolute paths from the file watcher
+        let root = std::env::current_dir()
+            .and_then(std::fs::canonicalize)
+            .unwrap_or_default();
+        let mut builder = GitignoreBuilder::new(root);
+
+        if config.gitignore && Path::new(GITIGNORE_FILE).is_file() {
+            if let Some(err) = builder.add(GITIGNORE_FILE) {
+                return fail!(IgnorePattern, GITIGNORE_FILE.to_string(), Box::new(err));
+            }
+        }
+
+        for pattern in &config.ignore {
+            try_unwrap!(
+                builder.add_line(None, pattern),
+                else Err(err) => return fail!(IgnorePattern, pattern.to_string(), Box::new(err)),
+            );
+        }
+
+        let matcher = try_unwrap!(
+            builder.build(),
+            else Err(err) => return fail!(IgnorePattern, GITIGNORE_FILE.to_string(), Box::new(err)),
+        );
+        Ok(Self { matcher })
+    }
+
+    /// Create a filter which does not ignore any files
+    #[cfg(all(test, feature = "watch"))]
+    pub fn empty() -> Self {
+        Self {
+            matcher: Gitignore::empty(),
+        }
+    }
+
+    /// Check if a file or folder should be ignored
+    ///
+    /// Parent folders are not checked
+    pub fn is_ignored(&self, path: impl AsRef<Path>, is_dir: bool) -> bool {
+        // Normalize repeated and trailing slashes
+        let path: std::path::PathBuf = path.as_ref().components().collect();
+        self.matcher.matched(path, is_dir).is_ignore()
+    }
+}
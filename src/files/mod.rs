@@ -1,3 +1,7 @@
+/// Ignore patterns for source files
+mod filter;
+/// Source files from disk or memory
+mod source;
 #[cfg(test)]
 mod tests;
 
@@ -5,6 +9,9 @@ use std::{fs, path::Path};
 
 use crate::{Config, Error, FileMap};
 
+pub use filter::FileFilter;
+pub use source::Source;
+
 /// Returns `Err` if source folders are not found in the working directory
 pub fn check_source_folders(config: &Config) -> Result<(), Error> {
     let src_folders = [&config.templates, &config.public, &config.styles];
@@ -20,10 +27,24 @@ pub fn check_source_folders(config: &Config) -> Result<(), Error> {
 ///
 /// Returns a hashmap of filepath strings (relative to the given directory), and file contents
 ///
+/// Files and folders ignored by the filter are skipped
+///
 /// Returns `Err` if cannot read a file or folder children
-pub fn read_folder_recurse(folder: &str) -> Result<FileMap, Error> {
+pub fn read_folder_recurse(folder: &str, filter: &FileFilter) -> Result<FileMap, Error> {
     let mut filemap = FileMap::new();
-    load_filemap(&mut filemap, folder, "")?;
+    load_filemap(&mut filemap, filter, folder, "", false)?;
+    Ok(filemap)
+}
+
+/// Read a folder recursively, and read every file contents
+///
+/// Same as [`read_folder_recurse`], but filepaths include the file extension
+pub fn read_folder_recurse_with_extensions(
+    folder: &str,
+    filter: &FileFilter,
+) -> Result<FileMap, Error> {
+    let mut filemap = FileMap::new();
+    load_filemap(&mut filemap, filter, folder, "", true)?;
     Ok(filemap)
 }
 
@@ -32,8 +53,16 @@ pub fn read_folder_recurse(folder: &str) -> Result<FileMap, Error> {
 /// - For every *file* in the given directory, read and insert to hashmap
 /// - For every *folder* in the given directory, recurse this function, with the 'parent' folder as this folder
 ///
+/// File extensions are removed from filepaths, unless `keep_extension` is `true`
+///
 /// Returns `Err` if cannot read a file or folder children
-fn load_filemap(map: &mut FileMap, root: &str, parent: &str) -> Result<(), Error> {
+fn load_filemap(
+    map: &mut FileMap,
+    filter: &FileFilter,
+    root: &str,
+    parent: &str,
+    keep_extension: bool,
+) -> Result<(), Error> {
     // Full path relative to working directory
     let full_path = format!("{root}/{parent}/");
 
@@ -53,14 +82,30 @@ fn load_filemap(map: &mut FileMap, root: &str, parent: &str) -> Result<(), Error
         // Normalize slashes in path
         let path = path.replace('\\', "/");
 
+        // Skip ignored files and folders
+        let is_dir = Path::new(&path).is_dir();
+        if filter.is_ignored(&path, is_dir) {
+            continue;
+        }
+
         // If child is a folder, recurse this function
-        if Path::new(&path).is_dir() {
-            load_filemap(map, root, &format!("{parent}{name}/"))?;
+        if is_dir {
+            load_filemap(
+                map,
+                filter,
+                root,
+                &format!("{parent}{name}/"),
+                keep_extension,
+            )?;
             continue;
         }
 
         // Get name (not file extension) of child file
-        let name = get_filename(name);
+        let name = if keep_extension {
+            name
+        } else {
+            get_template_name(name)
+        };
 
         // Read file contents
         let content = try_unwrap!(
@@ -75,27 +120,26 @@ fn load_filemap(map: &mut FileMap, root: &str, parent: &str) -> Result<(), Error
     Ok(())
 }
 
-/// Remove files recursively from build directory, and create empty folders to be filled
+/// Suffix of temporary folder next to build folder, which a new build is written to before swapping into place
+const TEMP_BUILD_SUFFIX: &str = ".unreact-new";
+/// Suffix of folder next to build folder, which the previous build is moved to while swapping
+const OLD_BUILD_SUFFIX: &str = ".unreact-old";
+
+/// Remove files recursively from a folder, and create empty folders to be filled
 ///
 /// All paths are treated relative to working directory
 ///
-/// 1. Removes build folder (`./build/` or otherwise specified), if exists
-/// 2. Creates build folder
-/// 3. Creates `styles/` and `public/` inside build folder
-/// 4. Copies all files from public source folder into `public/` inside build folder
-pub fn clean_build_dir(config: &Config, is_dev: bool) -> Result<(), Error> {
-    // Remove build folder (if exists)
-    if Path::new(&config.build).exists() {
-        try_unwrap!(
-            fs::remove_dir_all(&config.build),
-            else Err(err) => return io_fail!(RemoveDir, config.build.clone(), err),
-        );
-    }
+/// 1. Removes folder (such as temporary build folder), if exists
+/// 2. Creates folder
+/// 3. Creates styles and public subfolders inside folder (`styles/` and `public/` or otherwise specified)
+pub fn clean_build_dir(folder: &str, config: &Config) -> Result<(), Error> {
+    // Remove folder (if exists)
+    remove_build_dir(folder)?;
 
     // Create output folders (build and subfolders)
-    let out_folders = ["", "styles", "public"];
-    for folder in out_folders {
-        let path = format!("{}/{}/", config.build, folder);
+    let out_folders = ["", &config.build_styles, &config.build_public];
+    for subfolder in out_folders {
+        let path = format!("{folder}/{subfolder}/");
 
         try_unwrap!(
             fs::create_dir_all(&path),
@@ -103,30 +147,227 @@ pub fn clean_build_dir(config: &Config, is_dev: bool) -> Result<(), Error> {
         );
     }
 
-    // Public directory
-    // Only *copy* directory in production
-    if !is_dev {
-        // Recursively copy public directory
+    Ok(())
+}
+
+/// Get path of temporary folder next to build folder, such as `build.unreact-new` for `build`
+pub fn temp_build_dir(build: &str) -> String {
+    build.trim_end_matches(['/', '\\']).to_string() + TEMP_BUILD_SUFFIX
+}
+
+/// Replace build folder with temporary build folder, keeping previous build if replacing fails
+///
+/// 1. Moves previous build folder (if exists) to a folder next to it, such as `build.unreact-old`
+/// 2. Moves temporary folder to build folder, or moves previous build back if this fails
+/// 3. Removes previous build
+///
+/// Each move is an atomic rename, but the swap as a whole is not: the build folder does not exist between the two renames.
+/// A server reading the build folder at that moment may fail to find files
+///
+/// Returns `Err` with the path of the previous build, if it could not be moved back after failing to replace it
+pub fn swap_build_dir(temp: &str, build: &str) -> Result<(), Error> {
+    let build = build.trim_end_matches(['/', '\\']);
+    let old = format!("{build}{OLD_BUILD_SUFFIX}");
+
+    // Remove leftover folder from a previous swap
+    remove_build_dir(&old)?;
+
+    let has_previous = Path::new(build).exists();
+    if has_previous {
+        try_unwrap!(
+            fs::rename(build, &old),
+            else Err(err) => return io_fail!(RenameDir, build.to_string(), err),
+        );
+    }
+
+    if let Err(err) = fs::rename(temp, build) {
+        // Restore previous build
+        if has_previous {
+            if let Err(restore_err) = fs::rename(&old, build) {
+                return io_fail!(RestoreDir, temp.to_string(), err, old, restore_err);
+            }
+        }
+        return io_fail!(RenameDir, temp.to_string(), err);
+    }
+
+    remove_build_dir(&old)
+}
+
+/// Remove a build folder recursively, if it exists
+pub fn remove_build_dir(folder: &str) -> Result<(), Error> {
+    if Path::new(folder).exists() {
         try_unwrap!(
-            dircpy::copy_dir(&config.public, format!("{}/public", config.build)),
-            else Err(err) => return io_fail!(CopyDir, config.public.clone(), err),
+            fs::remove_dir_all(folder),
+            else Err(err) => return io_fail!(RemoveDir, folder.to_string(), err),
         );
-    } else {
-        // Create dummy note file
+    }
+    Ok(())
+}
+
+/// Write a file, creating parent folders if they do not exist
+pub fn write_file(path: &str, content: &[u8]) -> Result<(), Error> {
+    if let Some((parent, _)) = path.rsplit_once('/') {
         try_unwrap!(
-            fs::write(format!("{}/public/EMPTY", config.build), "'public' folder should always be empty in dev mode"),
-            else Err(err) => return io_fail!(CopyDir, config.public.clone(), err),
-        )
+            fs::create_dir_all(parent),
+            else Err(err) => return io_fail!(CreateDir, parent.to_string(), err),
+        );
     }
 
+    try_unwrap!(
+        fs::write(path, content),
+        else Err(err) => return io_fail!(WriteFile, path.to_string(), err),
+    );
     Ok(())
 }
 
+/// List all files of a folder recursively, with the size of each file in bytes
+///
+/// Filepaths are relative to the given directory, and include file extensions.
+/// Files and folders ignored by the filter are skipped
+///
+/// Returns `Err` if cannot read folder children, or file metadata
+pub fn list_folder_recurse(folder: &str, filter: &FileFilter) -> Result<Vec<(String, u64)>, Error> {
+    let mut files = Vec::new();
+    list_files(&mut files, filter, folder, "")?;
+    Ok(files)
+}
+
+/// List all files of a folder recursively into an existing list, skipping ignored files and folders
+///
+/// Filepaths are relative to `root`, and include file extensions
+fn list_files(
+    files: &mut Vec<(String, u64)>,
+    filter: &FileFilter,
+    root: &str,
+    parent: &str,
+) -> Result<(), Error> {
+    let from = format!("{root}/{parent}");
+
+    // Children of current directory
+    let children = try_unwrap!(
+        fs::read_dir(&from),
+        else Err(err) => return io_fail!(ReadDir, from, err),
+    );
+
+    for file in children.flatten() {
+        let path = file.path();
+        let Some(name) = file.file_name().to_str().map(String::from) else {
+            continue;
+        };
+
+        // Skip ignored files and folders
+        let is_dir = path.is_dir();
+        if filter.is_ignored(&path, is_dir) {
+            continue;
+        }
+
+        // If child is a folder, recurse this function
+        if is_dir {
+            list_files(files, filter, root, &format!("{parent}{name}/"))?;
+            continue;
+        }
+
+        let metadata = try_unwrap!(
+            file.metadata(),
+            else Err(err) => return io_fail!(ReadFile, path.display().to_string(), err),
+        );
+        files.push((format!("{parent}{name}"), metadata.len()));
+    }
+
+    Ok(())
+}
+
+/// Copy a file, creating parent folders if they do not exist
+///
+/// File contents are streamed, and permissions are kept
+///
+/// Returns number of bytes copied
+pub fn copy_file(from: &str, to: &str) -> Result<u64, Error> {
+    if let Some((parent, _)) = to.rsplit_once('/') {
+        try_unwrap!(
+            fs::create_dir_all(parent),
+            else Err(err) => return io_fail!(CreateDir, parent.to_string(), err),
+        );
+    }
+
+    Ok(try_unwrap!(
+        fs::copy(from, to),
+        else Err(err) => return io_fail!(CopyFile, from.to_string(), err),
+    ))
+}
+
+/// Normalize a path for comparison, removing `./` prefixes, repeated and trailing slashes, and resolving `..` where possible
+pub fn normalize_path(path: &str) -> String {
+    let mut parts = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => (),
+            ".." if parts.last().is_some_and(|last| *last != "..") => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Get path of `to`, relative to the folder `from`, such as `../../styles/global.scss`
+///
+/// Returns `to` as given if either path is absolute, or `to` relative to the current directory if `from` is above the current directory
+pub fn relative_path(from: &str, to: &str) -> String {
+    if Path::new(from).is_absolute() || Path::new(to).is_absolute() {
+        return to.to_string();
+    }
+
+    let from = normalize_path(from);
+    let to = normalize_path(to);
+    let from: Vec<_> = from.split('/').filter(|part| !part.is_empty()).collect();
+    let to: Vec<_> = to.split('/').filter(|part| !part.is_empty()).collect();
+
+    // Number of shared leading folders
+    let shared = from
+        .iter()
+        .zip(&to)
+        .take_while(|(from, to)| from == to)
+        .count();
+    // Folder names above the current directory are not known
+    if from[shared..].contains(&"..") {
+        return to.join("/");
+    }
+
+    let mut parts = vec![".."; from.len() - shared];
+    parts.extend(&to[shared..]);
+    parts.join("/")
+}
+
 /// Get file 'name' from full file
 ///
-/// Returns everything before the first `.` period
+/// Returns everything before the last `.` period, or the full name if there is no file extension.
+/// A leading `.` (such as in `.hidden`) is not treated as a file extension
+pub fn get_filename(full_name: &str) -> &str {
+    match full_name.rsplit_once('.') {
+        Some((name, _)) if !name.is_empty() => name,
+        _ => full_name,
+    }
+}
+
+/// Get template name from full file
+///
+/// Returns everything before the first `.` period, so `post.v2.hbs` is named `post`
 ///
 /// Returns empty string if nothing found
-pub fn get_filename(full_name: &str) -> &str {
+pub fn get_template_name(full_name: &str) -> &str {
     full_name.split('.').next().unwrap_or("")
 }
+
+/// Get file extension from full file, without the `.` period
+///
+/// Returns everything after the last `.` period of the file name, or empty string if there is no file extension.
+/// A leading `.` (such as in `.hidden`) is not treated as a file extension
+pub fn get_extension(full_name: &str) -> &str {
+    let file = full_name.rsplit('/').next().unwrap_or(full_name);
+    match file.rsplit_once('.') {
+        Some((name, extension)) if !name.is_empty() => extension,
+        _ => "",
+    }
+}
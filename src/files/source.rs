@@ -0,0 +1,247 @@
+use std::{collections::HashMap, io, path::Path};
+
+use super::{
+    check_source_folders, get_template_name, list_folder_recurse, normalize_path,
+    read_folder_recurse, read_folder_recurse_with_extensions, FileFilter,
+};
+use crate::{Config, Error, FileMap};
+
+/// Source files for templates, styles, and public files
+///
+/// Files are read from folders in the working directory by default.
+/// Use `Source::memory()` to supply files from memory, or embed a folder with `.include_dir(...)` (with `"include_dir"` feature)
+///
+/// Paths of files in memory are relative to the working directory, so folders in `Config` are used the same as on disk
+///
+/// # Examples
+///
+/// ```rust
+/// # use unreact::prelude::*;
+/// # fn main() -> Result<(), Error> {
+/// let source = Source::memory()
+///     .file("assets/templates/page.hbs", "<h1>Hello {{name}}</h1>")
+///     .file("assets/styles/global.scss", "h1 { color: red; }")
+///     .file("assets/public/robots.txt", "User-agent: *");
+///
+/// let mut app = Unreact::with_source(Config::default(), "https://example.com", source)?;
+/// app.index("page", object! { name: "World" })?;
+///
+/// let output = app.build_to_memory()?;
+/// assert!(output.contains_key("index.html"));
+/// assert!(output.contains_key("styles/global/style.css"));
+/// assert!(output.contains_key("public/robots.txt"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub enum Source {
+    /// Read files from folders in working directory
+    #[default]
+    Disk,
+    /// Files in memory, by path relative to working directory, such as `assets/templates/page.hbs`
+    Memory(HashMap<String, Vec<u8>>),
+}
+
+impl Source {
+    /// Create an empty source of files in memory
+    pub fn memory() -> Self {
+        Self::Memory(HashMap::new())
+    }
+
+    /// Add a file in memory, with path relative to working directory
+    ///
+    /// Replaces a `Disk` source with an empty memory source first
+    pub fn file(mut self, path: &str, content: impl Into<Vec<u8>>) -> Self {
+        self.files_mut()
+            .insert(normalize_path(path), content.into());
+        self
+    }
+
+    /// Add every file of a folder embedded with [`include_dir!`](include_dir::include_dir), in memory
+    ///
+    /// - `folder`: Path of embedded folder, relative to working directory, such as `assets`
+    ///
+    /// Replaces a `Disk` source with an empty memory source first
+    ///
+    /// Only available with `"include_dir"` feature
+    #[cfg(feature = "include_dir")]
+    pub fn include_dir(mut self, folder: &str, dir: &include_dir::Dir) -> Self {
+        /// Insert files of folder and subfolders
+        fn insert_files(
+            files: &mut HashMap<String, Vec<u8>>,
+            folder: &str,
+            dir: &include_dir::Dir,
+        ) {
+            for file in dir.files() {
+                let path = Path::new(folder).join(file.path());
+                let path = normalize_path(&path.to_string_lossy());
+                files.insert(path, file.contents().to_vec());
+            }
+            for dir in dir.dirs() {
+                insert_files(files, folder, dir);
+            }
+        }
+
+        insert_files(self.files_mut(), folder, dir);
+        self
+    }
+
+    /// Get files in memory, replacing a `Disk` source with an empty memory source
+    fn files_mut(&mut self) -> &mut HashMap<String, Vec<u8>> {
+        if let Self::Disk = self {
+            *self = Self::memory();
+        }
+        match self {
+            Self::Memory(files) => files,
+            Self::Disk => unreachable!("Source should be in memory"),
+        }
+    }
+
+    /// Returns `Err` if source folders are not found
+    ///
+    /// Folders in memory always exist, as they may be empty
+    pub(crate) fn check_folders(&self, config: &Config) -> Result<(), Error> {
+        match self {
+            Self::Disk => check_source_folders(config),
+            Self::Memory(_) => Ok(()),
+        }
+    }
+
+    /// Check if a file exists, with path relative to working directory
+    pub(crate) fn is_file(&self, path: &str) -> bool {
+        match self {
+            Self::Disk => Path::new(path).is_file(),
+            Self::Memory(files) => files.contains_key(&normalize_path(path)),
+        }
+    }
+
+    /// Read a folder recursively, and read every file contents
+    ///
+    /// Returns a hashmap of filepath strings (relative to the given directory), and file contents.
+    /// File extensions are removed from filepaths, unless `keep_extension` is `true`
+    ///
+    /// Files and folders ignored by the filter are skipped
+    pub(crate) fn read_folder(
+        &self,
+        folder: &str,
+        filter: &FileFilter,
+        keep_extension: bool,
+    ) -> Result<FileMap, Error> {
+        match self {
+            Self::Disk if keep_extension => read_folder_recurse_with_extensions(folder, filter),
+            Self::Disk => read_folder_recurse(folder, filter),
+
+            Self::Memory(_) => {
+                let mut filemap = FileMap::new();
+                for (name, content) in self.memory_folder(folder, filter) {
+                    let path = format!("{}/{name}", normalize_path(folder));
+                    let content = try_unwrap!(
+                        String::from_utf8(content.to_vec()),
+                        else Err(err) => return io_fail!(ReadFile, path, io::Error::new(io::ErrorKind::InvalidData, err)),
+                    );
+
+                    // Remove file extension, keeping parent folders
+                    let name = match (keep_extension, name.rsplit_once('/')) {
+                        (true, _) => name,
+                        (false, Some((parent, file))) => {
+                            format!("{parent}/{}", get_template_name(file))
+                        }
+                        (false, None) => get_template_name(&name).to_string(),
+                    };
+                    filemap.insert(name, content);
+                }
+                Ok(filemap)
+            }
+        }
+    }
+
+    /// Read a folder recursively, and read every file contents as bytes
+    ///
+    /// Returns a hashmap of filepath strings (relative to the given directory, with file extensions), and file contents
+    ///
+    /// Files and folders ignored by the filter are skipped
+    pub(crate) fn read_folder_bytes(
+        &self,
+        folder: &str,
+        filter: &FileFilter,
+    ) -> Result<HashMap<String, Vec<u8>>, Error> {
+        match self {
+            Self::Disk => {
+                let mut files = HashMap::new();
+                for (name, _) in list_folder_recurse(folder, filter)? {
+                    let path = format!("{folder}/{name}");
+                    let content = try_unwrap!(
+                        std::fs::read(&path),
+                        else Err(err) => return io_fail!(ReadFile, path, err),
+                    );
+                    files.insert(name, content);
+                }
+                Ok(files)
+            }
+
+            Self::Memory(_) => Ok(self
+                .memory_folder(folder, filter)
+                .map(|(name, content)| (name, content.to_vec()))
+                .collect()),
+        }
+    }
+
+    /// Get files in memory inside a folder, by path relative to folder, skipping ignored files and folders
+    fn memory_folder<'a>(
+        &'a self,
+        folder: &str,
+        filter: &'a FileFilter,
+    ) -> impl Iterator<Item = (String, &'a [u8])> + 'a {
+        let folder = normalize_path(folder) + "/";
+        let files = match self {
+            Self::Memory(files) => Some(files),
+            Self::Disk => None,
+        };
+
+        files
+            .into_iter()
+            .flatten()
+            .filter_map(move |(path, content)| {
+                let name = path.strip_prefix(&folder)?;
+
+                // Skip file if file or any parent folder (inside source folder) is ignored
+                let mut parent = folder.trim_end_matches('/').to_string();
+                let parts: Vec<_> = name.split('/').collect();
+                for (i, part) in parts.iter().enumerate() {
+                    parent = format!("{parent}/{part}");
+                    if filter.is_ignored(&parent, i + 1 < parts.len()) {
+                        return None;
+                    }
+                }
+
+                Some((name.to_string(), content.as_slice()))
+            })
+    }
+}
+
+/// Resolve `@use`, `@forward`, and `@import` rules in styles from source files
+impl grass::Fs for Source {
+    fn is_dir(&self, path: &Path) -> bool {
+        match self {
+            Self::Disk => path.is_dir(),
+            Self::Memory(files) => {
+                let folder = normalize_path(&path.to_string_lossy()) + "/";
+                files.keys().any(|file| file.starts_with(&folder))
+            }
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        Source::is_file(self, &path.to_string_lossy())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Disk => std::fs::read(path),
+            Self::Memory(files) => files
+                .get(&normalize_path(&path.to_string_lossy()))
+                .cloned()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound)),
+        }
+    }
+}
@@ -21,10 +21,11 @@ fn check_source_folders_works() {
 
 #[test]
 fn read_folder_recurse_works() {
-    let files = read_folder_recurse("tests/assets/styles/").unwrap();
+    let filter = FileFilter::new(&Config::default()).unwrap();
+    let files = read_folder_recurse("tests/assets/styles/", &filter).unwrap();
 
     println!("{:#?}", files);
-    assert_eq!(files.len(), 2);
+    assert_eq!(files.len(), 6);
     assert_eq!(
         files.get("global").unwrap(),
         include_str!("../../tests/assets/styles/global.scss")
@@ -33,6 +34,31 @@ fn read_folder_recurse_works() {
         files.get("scoped/stylish").unwrap(),
         include_str!("../../tests/assets/styles/scoped/stylish.scss")
     );
+    // Partials are read, but not compiled
+    assert_eq!(
+        files.get("_variables").unwrap(),
+        include_str!("../../tests/assets/styles/_variables.scss")
+    );
+}
+
+#[test]
+fn read_folder_recurse_with_extensions_works() {
+    let filter = FileFilter::new(&Config::default()).unwrap();
+    let files = read_folder_recurse_with_extensions("tests/assets/styles/", &filter).unwrap();
+
+    let mut names: Vec<_> = files.keys().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "_variables.scss",
+            "global.scss",
+            "plain.css",
+            "scoped/_colors.scss",
+            "scoped/indented.sass",
+            "scoped/stylish.scss",
+        ]
+    );
 }
 
 #[test]
@@ -45,13 +71,215 @@ fn clean_build_dir_works() {
         ..Config::default()
     };
 
-    assert!(clean_build_dir(&config, false).is_ok());
+    let filter = FileFilter::new(&config).unwrap();
+
+    assert!(clean_build_dir(&config.build, &config).is_ok());
+    assert!(Path::new("tests/build/public").is_dir());
+
+    let public = Source::Disk
+        .read_folder_bytes(&config.public, &filter)
+        .unwrap();
+    for (path, content) in &public {
+        write_file(&format!("tests/build/public/{path}"), content).unwrap();
+    }
+    assert!(Path::new("tests/build/public/b/c.txt").is_file());
+    // Ignored editor file
+    assert!(!Path::new("tests/build/public/a.txt.swp").exists());
+}
+
+#[test]
+fn memory_source_works() {
+    let config = Config {
+        ignore: vec!["*.swp".to_string(), "drafts/".to_string()],
+        gitignore: false,
+        ..Config::default()
+    };
+    let filter = FileFilter::new(&config).unwrap();
+
+    let source = Source::memory()
+        .file("./assets/templates/page.hbs", "page")
+        .file("assets/templates/other/article.hbs", "article")
+        .file("assets/templates/page.hbs.swp", "swap")
+        .file("assets/templates/drafts/unfinished.hbs", "draft")
+        .file("assets/public/image.png", [0xff, 0xd8]);
+
+    let templates = source
+        .read_folder("assets/templates/", &filter, false)
+        .unwrap();
+    let mut names: Vec<_> = templates.keys().collect();
+    names.sort();
+    assert_eq!(names, ["other/article", "page"]);
+    assert_eq!(templates["page"], "page");
+
+    let templates = source
+        .read_folder("assets/templates", &filter, true)
+        .unwrap();
+    assert!(templates.contains_key("other/article.hbs"));
+
+    let public = source.read_folder_bytes("assets/public", &filter).unwrap();
+    assert_eq!(public["image.png"], [0xff, 0xd8]);
+    // Invalid UTF-8
+    assert!(source.read_folder("assets/public", &filter, false).is_err());
+
+    assert!(source.is_file("assets/templates/page.hbs"));
+    assert!(!source.is_file("assets/templates"));
+    assert!(source.check_folders(&config).is_ok());
+    assert!(grass::Fs::is_dir(
+        &source,
+        Path::new("./assets/templates/other")
+    ));
+}
+
+#[test]
+fn file_filter_works() {
+    let config = Config {
+        ignore: vec![
+            "*.swp".to_string(),
+            "drafts/".to_string(),
+            "!keep.swp".to_string(),
+        ],
+        gitignore: false,
+        ..Config::default()
+    };
+    let filter = FileFilter::new(&config).unwrap();
+
+    assert!(filter.is_ignored("assets/templates/page.hbs.swp", false));
+    assert!(filter.is_ignored("assets/templates/drafts", true));
+    assert!(!filter.is_ignored("assets/templates/drafts", false));
+    assert!(!filter.is_ignored("assets/templates/keep.swp", false));
+    assert!(!filter.is_ignored("assets/templates/page.hbs", false));
+
+    // Anchored patterns match canonicalized paths
+    let config = Config {
+        ignore: vec!["/tests/assets/templates".to_string()],
+        gitignore: false,
+        ..Config::default()
+    };
+    let filter = FileFilter::new(&config).unwrap();
+    let templates = std::fs::canonicalize("tests/assets/templates").unwrap();
+    assert!(filter.is_ignored(&templates, true));
+    assert!(filter.is_ignored("tests/assets/templates", true));
+
+    let config = Config {
+        ignore: vec!["[".to_string()],
+        ..Config::default()
+    };
+    assert!(matches!(
+        FileFilter::new(&config),
+        Err(Error::IgnorePattern(pattern, _)) if pattern == "[",
+    ));
 }
 
 #[test]
 fn get_filename_works() {
     assert_eq!(get_filename("abc"), "abc");
     assert_eq!(get_filename("abc.txt"), "abc");
-    assert_eq!(get_filename("abc.def.txt"), "abc");
+    assert_eq!(get_filename("abc.def.txt"), "abc.def");
+    assert_eq!(get_filename(".hidden"), ".hidden");
     assert_eq!(get_filename(""), "");
 }
+
+#[test]
+fn get_template_name_works() {
+    assert_eq!(get_template_name("abc"), "abc");
+    assert_eq!(get_template_name("abc.hbs"), "abc");
+    assert_eq!(get_template_name("abc.v2.hbs"), "abc");
+    assert_eq!(get_template_name(""), "");
+}
+
+#[test]
+fn get_extension_works() {
+    assert_eq!(get_extension("abc"), "");
+    assert_eq!(get_extension("abc.txt"), "txt");
+    assert_eq!(get_extension("abc.def.scss"), "scss");
+    assert_eq!(get_extension(".hidden"), "");
+    assert_eq!(get_extension("v1.2/abc"), "");
+    assert_eq!(get_extension(""), "");
+}
+
+#[test]
+fn normalize_path_works() {
+    assert_eq!(normalize_path("./assets//styles/"), "assets/styles");
+    assert_eq!(
+        normalize_path("assets\\styles\\a.scss"),
+        "assets/styles/a.scss"
+    );
+    assert_eq!(
+        normalize_path("assets/styles/scoped/../_a.scss"),
+        "assets/styles/_a.scss"
+    );
+    assert_eq!(normalize_path("../static"), "../static");
+    assert_eq!(normalize_path("./"), "");
+}
+
+#[test]
+fn relative_path_works() {
+    assert_eq!(
+        relative_path("build/styles/global", "assets/styles/global.scss"),
+        "../../../assets/styles/global.scss"
+    );
+    assert_eq!(
+        relative_path("./assets/build", "assets/styles/a.scss"),
+        "../styles/a.scss"
+    );
+    assert_eq!(relative_path("", "assets/a.scss"), "assets/a.scss");
+    assert_eq!(relative_path("../build", "assets/a.scss"), "assets/a.scss");
+    assert_eq!(relative_path("build", "/srv/a.scss"), "/srv/a.scss");
+}
+
+#[test]
+fn swap_build_dir_works() {
+    let build = std::env::temp_dir().join("unreact-swap-test/build");
+    let build = build.to_str().unwrap();
+    let temp = temp_build_dir(build);
+    assert!(temp.ends_with("build.unreact-new"));
+
+    // No previous build
+    remove_build_dir(build).unwrap();
+    write_file(&format!("{temp}/index.html"), b"first").unwrap();
+    swap_build_dir(&temp, build).unwrap();
+    assert_eq!(fs::read(format!("{build}/index.html")).unwrap(), b"first");
+    assert!(!Path::new(&temp).exists());
+
+    // Previous build is replaced
+    write_file(&format!("{temp}/about/index.html"), b"second").unwrap();
+    swap_build_dir(&temp, &format!("{build}/")).unwrap();
+    assert_eq!(
+        fs::read(format!("{build}/about/index.html")).unwrap(),
+        b"second"
+    );
+    assert!(!Path::new(&format!("{build}/index.html")).exists());
+    assert!(!Path::new(&format!("{build}.unreact-old")).exists());
+
+    // Previous build is kept if temporary folder does not exist
+    assert!(swap_build_dir(&temp, build).is_err());
+    assert!(Path::new(&format!("{build}/about/index.html")).is_file());
+}
+
+#[test]
+fn copy_file_works() {
+    let folder = std::env::temp_dir().join("unreact-copy-test");
+    let folder = folder.to_str().unwrap();
+    remove_build_dir(folder).unwrap();
+
+    let from = format!("{folder}/script.sh");
+    write_file(&from, b"echo hi").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&from, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    // Parent folders are created
+    let to = format!("{folder}/build/public/script.sh");
+    assert_eq!(copy_file(&from, &to).unwrap(), 7);
+    assert_eq!(fs::read(&to).unwrap(), b"echo hi");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&to).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    remove_build_dir(folder).unwrap();
+}
@@ -72,6 +72,7 @@
 //!      │  └─ page.hbs
 //!      │
 //!      ├─ styles/
+//!      │  ├─ _variables.scss
 //!      │  ├─ global.scss
 //!      │  └─ scoped/
 //!      │     └─ stylish.scss
@@ -79,6 +80,10 @@
 //!      └─ public/
 //!         └─ favicon.ico
 //! ```
+//!
+//! Each `.scss`, `.sass`, or `.css` file in `styles/` is compiled to `build/styles/<name>/style.css` (configured with `Config::build_styles` and `Config::style_output`).
+//! Files starting with an underscore (like `_variables.scss`) are partials, which are not compiled alone,
+//! but can be loaded by other styles with `@use 'variables'`
 
 #![doc(html_logo_url = "https://raw.githubusercontent.com/darccyy/unreact/main/icon.png")]
 #![doc(html_favicon_url = "https://raw.githubusercontent.com/darccyy/unreact/main/icon.png")]
@@ -88,6 +93,8 @@
 mod macros;
 /// `Unreact` struct implementations
 mod app;
+/// Command-line arguments
+mod args;
 /// `Config` struct
 mod config;
 /// Convert and render filetypes, .hbs and .scss
@@ -96,17 +103,26 @@ mod convert;
 mod error;
 /// Handle file system logic
 mod files;
+/// Report of files produced by a compile
+mod report;
 
 /// Dev server and websockets
 #[cfg(feature = "dev")]
 mod server;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 use handlebars::Handlebars;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::files::FileFilter;
 
 pub use crate::{
-    config::Config,
+    args::{Args, Command},
+    config::{Config, LinkCheck},
     error::{Error, IoError},
+    files::Source,
+    report::{BuildReport, FileReport, RouteReport},
 };
 
 pub use handlebars;
@@ -121,8 +137,9 @@ pub use serde_json::{json, Value};
 /// - [`object`] macro
 /// - [`is_dev`](fn.is_dev.html) function
 /// - [`Error`] enum
+/// - [`Source`] enum
 pub mod prelude {
-    pub use crate::{is_dev, object, Config, Error, Unreact};
+    pub use crate::{is_dev, object, Config, Error, Source, Unreact};
 }
 
 /// Represents json-like object
@@ -137,6 +154,12 @@ pub type Object = serde_json::Map<String, Value>;
 type FileMap = HashMap<String, String>;
 /// Map a path to a `Page` enum
 type RouteMap = HashMap<String, Page>;
+/// List of files to copy, as a source path, and an output path (relative to build directory)
+type CopyList = Vec<(String, String)>;
+/// Map an output path (relative to build directory) to file contents
+///
+/// Returned by [`Unreact::build_to_memory`]
+pub type OutputMap = BTreeMap<String, Vec<u8>>;
 
 /// Build directory for *dev mode*
 ///
@@ -149,7 +172,7 @@ const DEV_BUILD_DIR: &str = ".devbuild";
 ///
 /// - `Raw`: Raw string
 /// - `Template`: Render a template, with data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Page {
     /// Raw string
     Raw(String),
@@ -187,6 +210,10 @@ pub struct Unreact<'a> {
     routes: RouteMap,
     /// Global variables for templates
     globals: Object,
+    /// Global variables for routes under a path prefix, by prefix
+    ///
+    /// Sorted, so shorter prefixes are merged first
+    scoped_globals: BTreeMap<String, Object>,
     /// Whether *dev mode* is active
     is_dev: bool,
     /// [`Handlebars`](handlebars) registry
@@ -197,6 +224,40 @@ pub struct Unreact<'a> {
     ///
     /// Access with `.url()` method
     url: String,
+    /// Filter for ignored source files
+    filter: FileFilter,
+    /// Callback to create routes, ran before every compile
+    ///
+    /// Set with `.route_builder()` method
+    route_builder: Option<RouteBuilder<'a>>,
+    /// Routes created by the last run of route builder, with the route each one replaced (if any)
+    ///
+    /// Removed (or restored) before route builder is ran again, so routes created outside of route builder are kept
+    built_routes: HashMap<String, Option<Page>>,
+    /// Whether route builder is currently running
+    building: bool,
+    /// [`Handlebars`](handlebars) registry with custom templates, cached for `.render()` method
+    render_registry: Option<Handlebars<'a>>,
+    /// Source files for templates, styles, and public files
+    ///
+    /// Set with `Unreact::with_source`
+    source: Source,
+    /// Command to run with `.run()` method
+    ///
+    /// Set from command-line arguments with `Unreact::from_args`
+    command: Command,
+}
+
+/// Callback to create routes, ran before every compile
+struct RouteBuilder<'a>(Box<RouteBuilderFn<'a>>);
+
+/// Function signature of [`RouteBuilder`]
+type RouteBuilderFn<'a> = dyn Fn(&mut Unreact<'a>) -> Result<(), Error> + 'a;
+
+impl std::fmt::Debug for RouteBuilder<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RouteBuilder")
+    }
 }
 
 /// Check if `--dev` or `-d` argument was passed on `cargo run`
@@ -215,11 +276,29 @@ pub struct Unreact<'a> {
 /// cargo run -- --dev
 /// cargo run -- -d
 /// ```
+///
+/// See [`Unreact::from_args`](struct.Unreact.html#method.from_args) for more command-line arguments
 pub fn is_dev() -> bool {
     let args = std::env::args().collect::<Vec<_>>();
     args.contains(&"--dev".to_string()) || args.contains(&"-d".to_string())
 }
 
+/// Default patterns of source files to ignore
+///
+/// Common editor swap, backup, and lock files, and system metadata files
+const DEFAULT_IGNORE: &[&str] = &[
+    "*.swp",
+    "*.swo",
+    "*.swx",
+    "*~",
+    ".#*",
+    "\\#*#",
+    "4913",
+    ".DS_Store",
+    "Thumbs.db",
+    "desktop.ini",
+];
+
 /// Alias for u16
 type Port = u16;
 
@@ -227,6 +306,12 @@ type Port = u16;
 const DEFAULT_PORT: Port = 3000;
 /// Local port to host websocket hub (on localhost)
 const DEFAULT_PORT_WS: Port = 3001;
+/// Time to wait, in milliseconds, for more file changes before recompiling
+const DEFAULT_WATCH_DEBOUNCE: u64 = 300;
+/// Output path of each stylesheet, relative to `styles` in build directory
+const DEFAULT_STYLE_OUTPUT: &str = "{name}/style.css";
+/// Minimum size of output files to compress, in bytes
+const DEFAULT_COMPRESS_MIN_SIZE: usize = 1024;
 
 /// Get package name from `Cargo.toml` file in workspace
 ///
@@ -235,46 +320,9 @@ const DEFAULT_PORT_WS: Port = 3001;
 fn get_package_name() -> Option<String> {
     // Read Cargo.toml or return
     let file = std::fs::read_to_string("./Cargo.toml").ok()?;
+    let manifest: toml::Value = toml::from_str(&file).ok()?;
 
-    // Current category is 'package'
-    let mut is_package = false;
-    // Loop lines
-    for line in file.lines() {
-        let line = line.trim();
-
-        // Change category
-        if line.starts_with('[') && line.ends_with(']') {
-            is_package = line == "[package]";
-        }
-        // Continue if not package category
-        if !is_package {
-            continue;
-        }
-
-        // Check key is 'name'
-        let mut split = line.split('=');
-        if split.next().map(|x| x.trim()) != Some("name") {
-            continue;
-        }
-        let rest: Vec<_> = split.collect();
-
-        // Get rest of line
-        let name = rest.join("=");
-        let name = name.trim();
-
-        // Remove first and last characters, break if not quotes
-        let mut chars = name.chars();
-        if chars.next() != Some('"') {
-            break;
-        }
-        if chars.next_back() != Some('"') {
-            break;
-        }
-
-        // Return value
-        return Some(chars.as_str().to_string());
-    }
-
-    // No name found
-    None
+    // Get `name` key in `[package]` table
+    let name = manifest.get("package")?.get("name")?.as_str()?;
+    Some(name.to_string())
 }
@@ -0,0 +1,204 @@
+use std::{fmt, time::Duration};
+
+use serde::{Serialize, Serializer};
+
+use crate::{files::write_file, Error};
+
+/// Report of files produced by a compile, returned by [`Unreact::build`](struct.Unreact.html#method.build)
+///
+/// Written as JSON if `report` is set in config, and printed as a table by the *dev server*
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BuildReport {
+    /// Rendered routes, sorted by path
+    pub routes: Vec<RouteReport>,
+    /// Compiled stylesheets, sorted by name
+    pub styles: Vec<FileReport>,
+    /// Copied public files, sorted by path
+    ///
+    /// Empty in *dev mode*, as public files are served from the source folder
+    pub public: Vec<FileReport>,
+    /// Number of files written, including source maps and compressed copies
+    pub files: usize,
+    /// Total size of files written, in bytes
+    pub total_size: usize,
+    /// Time taken to compile and write every file
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+    /// Possible problems with the build, which are not errors
+    pub warnings: Vec<String>,
+}
+
+/// Report of a rendered route
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RouteReport {
+    /// Path of route, such as `about` (empty for index route)
+    pub path: String,
+    /// Output file, relative to build directory
+    pub output: String,
+    /// Name of template, or `None` for raw routes
+    pub template: Option<String>,
+    /// Time taken to render page
+    #[serde(rename = "render_ms", serialize_with = "as_millis")]
+    pub render_time: Duration,
+    /// Size of page before minifying, in bytes
+    pub raw_size: usize,
+    /// Size of page as written, in bytes
+    ///
+    /// Measured after minifying, inlining critical CSS, adding base path to links, and adding dev script
+    pub size: usize,
+}
+
+/// Report of a compiled stylesheet, or copied public file
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FileReport {
+    /// Name of stylesheet, or path of public file relative to public folder
+    pub name: String,
+    /// Output file, relative to build directory
+    pub output: String,
+    /// Size of file written, in bytes
+    pub size: usize,
+}
+
+impl BuildReport {
+    /// Convert report to pretty JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Build report should serialize")
+    }
+
+    /// Write report as JSON to a file, creating parent folders if they do not exist
+    pub fn write_json(&self, path: &str) -> Result<(), Error> {
+        write_file(path, self.to_json().as_bytes())
+    }
+}
+
+/// Print report as a table of routes and stylesheets, with totals and warnings
+impl fmt::Display for BuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path_width = self
+            .routes
+            .iter()
+            .map(|route| display_path(&route.path).len())
+            .chain(self.styles.iter().map(|style| style.name.len()))
+            .chain(["Route".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:<path_width$}  {:>10}  {:>10}  {:>10}",
+            "Route", "Raw", "Size", "Time"
+        )?;
+        for route in &self.routes {
+            writeln!(
+                f,
+                "{:<path_width$}  {:>10}  {:>10}  {:>10}",
+                display_path(&route.path),
+                format_size(route.raw_size),
+                format_size(route.size),
+                format_duration(route.render_time),
+            )?;
+        }
+
+        if !self.styles.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{:<path_width$}  {:>10}", "Style", "Size")?;
+            for style in &self.styles {
+                writeln!(
+                    f,
+                    "{:<path_width$}  {:>10}",
+                    style.name,
+                    format_size(style.size)
+                )?;
+            }
+        }
+
+        writeln!(f)?;
+        write!(
+            f,
+            "{} routes, {} styles, {} public files | {} files, {} total | {}",
+            self.routes.len(),
+            self.styles.len(),
+            self.public.len(),
+            self.files,
+            format_size(self.total_size),
+            format_duration(self.duration),
+        )?;
+
+        for warning in &self.warnings {
+            write!(f, "\nWarning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Show index route as `/`, and other routes with a leading slash
+fn display_path(path: &str) -> String {
+    format!("/{path}")
+}
+
+/// Format a size in bytes, such as `512 B` or `1.5 KB`
+fn format_size(size: usize) -> String {
+    if size < 1024 {
+        format!("{size} B")
+    } else if size < 1024 * 1024 {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", size as f64 / 1024.0 / 1024.0)
+    }
+}
+
+/// Format a duration in milliseconds, such as `1.25 ms`
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Serialize a duration as a number of milliseconds
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_size_works() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+
+    #[test]
+    fn build_report_works() {
+        let report = BuildReport {
+            routes: vec![RouteReport {
+                path: "about".to_string(),
+                output: "about/index.html".to_string(),
+                template: Some("page".to_string()),
+                render_time: Duration::from_micros(1500),
+                raw_size: 2048,
+                size: 1024,
+            }],
+            files: 1,
+            total_size: 1024,
+            duration: Duration::from_millis(3),
+            warnings: vec!["No index route".to_string()],
+            ..BuildReport::default()
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["routes"][0]["render_ms"], 1.5);
+        assert_eq!(json["routes"][0]["template"], "page");
+        assert_eq!(json["duration_ms"], 3.0);
+
+        assert_eq!(
+            report.to_string(),
+            "\
+Route          Raw        Size        Time
+/about      2.0 KB      1.0 KB     1.50 ms
+
+1 routes, 0 styles, 0 public files | 1 files, 1.0 KB total | 3.00 ms
+Warning: No index route"
+        );
+    }
+}
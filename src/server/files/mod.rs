@@ -1,28 +1,59 @@
-use crate::Port;
+use crate::Config;
 
-/// Html file with javascript websockets to append to every file
+/// Html file with javascript (no websockets) to append to every file, when files are not watched
 ///
 /// **Should NOT be used without including port** (for parity with `"watch"` feature)
-#[cfg(not(feature = "watch"))]
-const DEV_SCRIPT_RAW: &str = include_str!("no-watch.html");
+const DEV_SCRIPT_NO_WATCH: &str = include_str!("no-watch.html");
 
-/// Html file with javascript (no websockets) to append to every file
+/// Html file with javascript websockets to append to every file, when files are watched
 ///
 /// **Should NOT be used without including port**
 #[cfg(feature = "watch")]
-const DEV_SCRIPT_RAW: &str = include_str!("watch.html");
+const DEV_SCRIPT_WATCH: &str = include_str!("watch.html");
 
-/// Fallback page, including dev script
+/// Fallback page, without dev script
+const FALLBACK_404_RAW: &str = include_str!("404.html");
+
+/// Get raw dev script, depending on whether files are watched
 ///
-/// **Should NOT be used without including port**
-const FALLBACK_404_RAW: &str = const_str::concat!(include_str!("404.html"), "\n\n", DEV_SCRIPT_RAW);
+/// Files are never watched without `"watch"` feature
+fn raw_script(
+    // Only for "watch" feature
+    #[allow(unused_variables)] watch: bool,
+) -> &'static str {
+    #[cfg(feature = "watch")]
+    {
+        if watch {
+            return DEV_SCRIPT_WATCH;
+        }
+    }
+    DEV_SCRIPT_NO_WATCH
+}
+
+/// Get dev script, with websocket port and style output paths included
+pub fn dev_script(config: &Config) -> String {
+    fill_script(raw_script(config.watch), config)
+}
 
-/// Get dev script, with websocket port included
-pub fn dev_script(port_ws: Port) -> String {
-    DEV_SCRIPT_RAW.replace("{{PORT}}", &port_ws.to_string())
+/// Get fallback 404 page, with dev script and websocket port included
+pub fn fallback_404(config: &Config) -> String {
+    let raw = format!("{FALLBACK_404_RAW}\n\n{}", raw_script(config.watch));
+    fill_script(&raw, config)
 }
 
-/// Get fallback 404 page, with websocket port included
-pub fn fallback_404(port_ws: Port) -> String {
-    FALLBACK_404_RAW.replace("{{PORT}}", &port_ws.to_string())
+/// Replace websocket port and style output paths (as json strings) in dev script
+fn fill_script(raw: &str, config: &Config) -> String {
+    raw.replace("{{PORT}}", &config.port_ws.to_string())
+        .replace(
+            "{{BASE_PATH}}",
+            &serde_json::json!(config.base_path).to_string(),
+        )
+        .replace(
+            "{{STYLE_FOLDER}}",
+            &serde_json::json!(config.build_styles).to_string(),
+        )
+        .replace(
+            "{{STYLE_OUTPUT}}",
+            &serde_json::json!(config.style_output).to_string(),
+        )
 }
@@ -7,26 +7,34 @@ mod files;
 #[cfg(feature = "watch")]
 mod watch;
 
-use std::{convert::Infallible, fs, path::Path};
+use std::{convert::Infallible, fs, path::Path, sync::Arc};
 
-use http::{Method, Request, Response, StatusCode};
+use http::{
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING, VARY},
+    Method, Request, Response, StatusCode,
+};
 use hyper::{
     service::{make_service_fn, service_fn},
     Body, Server,
 };
 
-use crate::{Port, DEV_BUILD_DIR};
+use crate::{Config, DEV_BUILD_DIR};
 
 pub use files::{dev_script, fallback_404};
 #[cfg(feature = "watch")]
-pub use watch::watch;
+pub use watch::{watch, Change, Folder, Rebuilt};
+
+/// File extensions of compressed copies of files, and their encoding names, in order of preference
+const COMPRESSED_FILES: &[(&str, &str)] = &[("br", "br"), ("gz", "gzip")];
 
 /// Create server and listen on localhost port
 ///
 /// Similar to GitHub Pages router
 ///
 /// Reads file on every request: this should not be a problem for a dev server
-pub fn listen(port: Port, public: &str, port_ws: Port) {
+///
+/// Uses port, folders, and build subfolders of config
+pub fn listen(config: &Config) {
     // Create runtime
     let runtime = unwrap!(
         tokio::runtime::Builder::new_current_thread()
@@ -35,19 +43,20 @@ pub fn listen(port: Port, public: &str, port_ws: Port) {
         "Failed to build tokio runtime"
     );
 
-    let public = public.to_string();
+    let port = config.port;
+    let config = Arc::new(config.clone());
 
     // Block on server running
     unwrap!(
         runtime.block_on(async {
             // Create service for router
-            // Moves `public`
+            // Moves `config`
             let make_svc =
             make_service_fn(move |_| {
-                let public = public.clone();
+                let config = config.clone();
                 async move {
                     Ok::<_, Infallible>(service_fn(move |req| {
-                        server_router(req, public.clone(), port_ws)
+                        server_router(req, config.clone())
                     }))
                 }
             });
@@ -71,62 +80,142 @@ pub fn listen(port: Port, public: &str, port_ws: Port) {
 /// Accepts '/foo', '/foo.html', and '/foo/index.html' patterns
 ///
 /// If no possible file was found, use 404 route (same as <URL>/404 request).
-/// If no custom 404 page was found, use fallback 404 page
+/// If no custom 404 page was found, use fallback 404 page.
+/// Paths outside of base path (if configured) also use 404 route
+///
+/// With `serve_compressed` in config, compressed copies of files are served if they exist, and the client accepts them
 async fn server_router(
     req: Request<Body>,
-    public: String,
-    port_ws: Port,
+    config: Arc<Config>,
 ) -> Result<Response<Body>, Infallible> {
-    // Check if is GET request
-    if req.method() == Method::GET {
-        let path = req.uri().path();
+    // Encodings accepted by client, for compressed files
+    let accept_encoding = req
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
 
+    // Check if is GET request, and path is under base path
+    let path = strip_base_path(req.uri().path(), &config.base_path);
+    if let (&Method::GET, Some(path)) = (req.method(), path) {
         // Map public files to source public folder
-        if path.starts_with("/public/") {
-            let path = path.replacen("/public", &public, 1);
+        if let Some(file) = path.strip_prefix(&format!("/{}/", config.build_public)) {
+            let path = format!("{}/{file}", config.public);
             return Ok(Response::new(read_and_unwrap(&path)));
         }
 
         // Return corresponding file as body if exists
-        // Routes everything but `/public/` files
-        if let Some(file) = get_best_possible_file(path) {
-            return Ok(Response::new(file));
+        // Routes everything but public files
+        if let Some(file) = get_best_possible_file(path, &config.build_styles) {
+            return Ok(file_response(
+                &file,
+                StatusCode::OK,
+                accept_encoding,
+                &config,
+            ));
         }
     }
 
     // 404 route
+    if let Some(file) = get_best_possible_file("/404.html", &config.build_styles) {
+        // If custom 404 route is defined (requesting route `/404.html`)
+        return Ok(file_response(
+            &file,
+            StatusCode::NOT_FOUND,
+            accept_encoding,
+            &config,
+        ));
+    }
+
+    // Fallback 404 response
     Ok(unwrap!(
-        Response::builder().status(StatusCode::NOT_FOUND).body(
-            if let Some(file) = get_best_possible_file("/404.html") {
-                // If custom 404 route is defined (requesting route `/404.html`)
-                file
-            } else {
-                // Fallback 404 response
-                Body::from(fallback_404(port_ws))
-            },
-        ),
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(fallback_404(&config))),
         // Should not error
         err: "Failed to build 404 route response `{err:?}`",
     ))
 }
 
-/// Loops through files in `possible_path_suffixes` to find best file match, and reads file
+/// Create response with contents of file
+///
+/// If `serve_compressed` is enabled in config, a compressed copy of the file (such as `index.html.br`) is used instead,
+/// if it exists and the client accepts the encoding
+fn file_response(
+    path: &str,
+    status: StatusCode,
+    accept_encoding: &str,
+    config: &Config,
+) -> Response<Body> {
+    let mut response = Response::builder().status(status);
+    let mut path = path.to_string();
+
+    if config.serve_compressed {
+        response = response.header(VARY, "Accept-Encoding");
+
+        let compressed = COMPRESSED_FILES.iter().find(|(extension, encoding)| {
+            accepts_encoding(accept_encoding, encoding)
+                && Path::new(&format!("{path}.{extension}")).is_file()
+        });
+        if let Some((extension, encoding)) = compressed {
+            response = response.header(CONTENT_ENCODING, *encoding);
+            path = format!("{path}.{extension}");
+        }
+    }
+
+    unwrap!(
+        response.body(read_and_unwrap(&path)),
+        // Should not error
+        err: "Failed to build response for '{path}' `{err:?}`",
+    )
+}
+
+/// Check if an encoding is accepted, from the value of an `Accept-Encoding` header
 ///
-/// Returns as `Option<Body>`, to allow non-UTF-8 file formats (such as images).
+/// Encodings with a quality of `0` (such as `gzip;q=0`) are not accepted
+fn accepts_encoding(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut parts = item.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let quality = parts
+            .find_map(|part| part.strip_prefix("q="))
+            .and_then(|quality| quality.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && quality > 0.0
+    })
+}
+
+/// Remove base path from start of request path
+///
+/// Returns `None` if path is not under base path.
+/// If base path is empty, path is returned unchanged
+fn strip_base_path<'a>(path: &'a str, base_path: &str) -> Option<&'a str> {
+    if base_path.is_empty() {
+        return Some(path);
+    }
+
+    let rest = path.strip_prefix('/')?.strip_prefix(base_path)?;
+    if rest.is_empty() {
+        return Some("/");
+    }
+    rest.starts_with('/').then_some(rest)
+}
+
+/// Loops through files in `possible_path_suffixes` to find best file match
+///
+/// Returns path of file in *dev mode* build directory.
 /// Returns `None` if no files were found
 ///
-/// Panics if file exists, but was unable to be read
-fn get_best_possible_file(path: &str) -> Option<Body> {
-    let possible_suffixes = possible_path_suffixes(path);
+/// - `build_styles`: Styles subfolder of build directory
+fn get_best_possible_file(path: &str, build_styles: &str) -> Option<String> {
+    let possible_suffixes = possible_path_suffixes(path, build_styles);
 
     for suffix in possible_suffixes {
-        let path = &format!("{DEV_BUILD_DIR}{path}{suffix}");
+        let path = format!("{DEV_BUILD_DIR}{path}{suffix}");
 
         // If file exists, and not directory
-        if Path::new(path).is_file() {
-            // Returns file content as `Body`
-            // Automatically parses to string, if is valid UTF-8, otherwise uses buffer
-            return Some(read_and_unwrap(path));
+        if Path::new(&path).is_file() {
+            return Some(path);
         }
     }
     None
@@ -134,6 +223,8 @@ fn get_best_possible_file(path: &str) -> Option<Body> {
 
 /// Read file and convert to body
 ///
+/// Uses buffer, to allow non-UTF-8 file formats (such as images)
+///
 /// Panics if IO error occurs
 fn read_and_unwrap(path: &str) -> Body {
     Body::from(unwrap!(
@@ -146,18 +237,44 @@ fn read_and_unwrap(path: &str) -> Body {
 
 /// Gets the possible path 'suffixes' from the path string
 ///
-/// If path ends with '.html', or starts with '/styles', then return a slice of an empty string.
+/// If path ends with '.html', or starts with the styles subfolder (`/styles/` or otherwise specified), then return a slice of an empty string.
 /// This path should refer to a file literally
 ///
 /// Otherwise, return a slice of: an empty string (for a literal file), '.html', and '/index.html' (for file path shorthand).
 /// Suffixes are returned in that order, to match a file based on specificity
 ///
-/// Paths starting with `/public/` should never be routed through here
-//TODO Make error for /public/ routing here
-fn possible_path_suffixes(path: &str) -> &'static [&'static str] {
-    if path.ends_with(".html") || path.starts_with("/styles/") {
+/// Paths starting with the public subfolder (`/public/` or otherwise specified) should never be routed through here
+//TODO Make error for public routing here
+fn possible_path_suffixes(path: &str, build_styles: &str) -> &'static [&'static str] {
+    if path.ends_with(".html") || path.starts_with(&format!("/{build_styles}/")) {
         &[""]
     } else {
         &["", ".html", "/index.html"]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_base_path_works() {
+        assert_eq!(strip_base_path("/about", ""), Some("/about"));
+        assert_eq!(strip_base_path("/project", "project"), Some("/"));
+        assert_eq!(strip_base_path("/project/", "project"), Some("/"));
+        assert_eq!(strip_base_path("/project/about", "project"), Some("/about"));
+        assert_eq!(strip_base_path("/projects/about", "project"), None);
+        assert_eq!(strip_base_path("/about", "project"), None);
+    }
+
+    #[test]
+    fn accepts_encoding_works() {
+        assert!(accepts_encoding("gzip, deflate, br", "br"));
+        assert!(accepts_encoding("gzip, deflate, br", "gzip"));
+        assert!(accepts_encoding("br;q=1.0, gzip;q=0.8", "gzip"));
+        assert!(accepts_encoding("*", "br"));
+        assert!(!accepts_encoding("gzip, deflate", "br"));
+        assert!(!accepts_encoding("br;q=0, gzip", "br"));
+        assert!(!accepts_encoding("", "gzip"));
+    }
+}
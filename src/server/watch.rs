@@ -1,26 +1,82 @@
 use std::{
-    collections::HashMap,
-    path::Path,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
     sync::{mpsc::channel, Arc, Mutex},
     thread,
     time::Duration,
 };
 
 use chrono::Utc;
-use notify::{EventKind, RecursiveMode, Watcher};
+use notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
 use simple_websockets::{Event, Message, Responder};
 
-use crate::Port;
+use crate::{
+    convert::is_partial,
+    files::{get_filename, get_template_name, FileFilter},
+    Port,
+};
 
-/// Minimum time to wait, in milliseconds, since the last event, for the websocket hub to send a reload request to the client
-const MIN_RECOMPILE_INTERVAL: u32 = 1000;
-/// Time to wait, in milliseconds, before reading a recently saved file
-const FILE_SAVE_WAIT: u64 = 300;
+/// Source folder that a watched file belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Folder {
+    /// Handlebars templates
+    Templates,
+    /// Scss stylesheets
+    Styles,
+    /// Static public files
+    Public,
+    /// Extra paths, such as data files
+    Other,
+}
+
+/// A file change, classified by source folder
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Source folder of changed file
+    pub folder: Folder,
+    /// Name of changed file, relative to the source folder, without file extension
+    pub name: String,
+}
+
+/// Routes which were rebuilt by the callback app router
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rebuilt {
+    /// Any route may have changed
+    All,
+    /// Only these routes changed
+    Routes(Vec<String>),
+}
+
+/// A connected websocket client
+struct Client {
+    /// Handler to send messages
+    responder: Responder,
+    /// Path of page currently open, if reported yet
+    path: Option<String>,
+}
 
 /// Initialize websocket hub, with callback app router, and watch files for changes
-pub fn watch<F>(router: F, watched_folders: &[&str], port: Port)
-where
-    F: Fn(),
+///
+/// Clients report the path of their page with a `path:<path>` message on connect.
+///
+/// If only stylesheets were changed, clients are sent a `style:<name>` message for each changed stylesheet,
+/// to swap the stylesheet without reloading the page.
+/// If the router only rebuilt some routes, clients showing one of those routes (or not yet reporting a path) are sent a `reload:<routes>` message,
+/// with the routes as a json array.
+/// Otherwise, clients are sent a `reload` message
+///
+/// Changes are debounced by the file watcher, and recompiled once no more changes are received for the `debounce` duration.
+/// Changes to ignored files are skipped
+pub fn watch<F>(
+    mut router: F,
+    watched_folders: &[(Folder, String)],
+    filter: &FileFilter,
+    port: Port,
+    debounce: Duration,
+) where
+    F: FnMut(&[Change]) -> Rebuilt,
 {
     // Initialize websocket hub
     let event_hub = unwrap!(
@@ -30,7 +86,7 @@ where
     );
 
     // List of connected clients, with ID and handler
-    let clients = Arc::new(Mutex::new(HashMap::<u64, Responder>::new()));
+    let clients = Arc::new(Mutex::new(HashMap::<u64, Client>::new()));
 
     // Last time the server was started
     let last_server_start = Utc::now().timestamp();
@@ -51,7 +107,22 @@ where
                     // Send message with last server start
                     responder.send(Message::Text(last_server_start.to_string()));
                     // Add client to list
-                    clients.insert(id, responder);
+                    clients.insert(
+                        id,
+                        Client {
+                            responder,
+                            path: None,
+                        },
+                    );
+                }
+
+                // Client reported the path of its page
+                Event::Message(id, Message::Text(text)) => {
+                    if let (Some(path), Some(client)) =
+                        (text.strip_prefix("path:"), clients.get_mut(&id))
+                    {
+                        client.path = Some(path.to_string());
+                    }
                 }
 
                 // Client disconnected, remove from list
@@ -70,55 +141,312 @@ where
     // Create event handler (channel)
     let (tx, rx) = channel();
 
-    // Create file watcher
-    let mut watcher =
-        unwrap!(notify::recommended_watcher(tx), err: "Could not create file watcher `{err:?}`");
+    // Create file watcher, which debounces events for each file
+    let mut debouncer = unwrap!(
+        new_debouncer(debounce, None, tx),
+        err: "Could not create file watcher `{err:?}`"
+    );
 
-    // Watch specific folders
-    for folder in watched_folders {
+    // Watch specific folders and files
+    for (_, folder) in watched_folders {
         unwrap!(
-            watcher.watch(Path::new(folder), RecursiveMode::Recursive),
+            debouncer.watcher().watch(Path::new(folder), RecursiveMode::Recursive),
             err: "Could not watch folder '{}' `{err:?}`", folder
         )
     }
 
-    // Last time the files were compiled
-    let mut last_compile = Utc::now().timestamp();
+    // Absolute paths of watched folders, to classify changed files
+    let watched_folders: Vec<_> = watched_folders
+        .iter()
+        .map(|(kind, folder)| {
+            let path = unwrap!(
+                fs::canonicalize(folder),
+                err: "Could not get absolute path of folder '{}' `{err:?}`", folder
+            );
+            (*kind, path)
+        })
+        .collect();
 
-    loop {
-        // If file change event message is ok
-        let Ok(Ok(event)) = rx.recv() else {
-            continue;
-        };
+    // Wait for debounced changes
+    // Any changes made while recompiling are received on the next loop, so the last change always causes a recompile
+    while let Ok(events) = rx.recv() {
+        let mut paths = HashSet::new();
+        add_event_paths(&mut paths, events);
 
-        // If file event is: a created, modified, or removed file
-        if !matches!(
-            event.kind,
-            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
-        ) {
-            continue;
+        // Include any other batches already received, without waiting again, as events are already debounced
+        while let Ok(events) = rx.try_recv() {
+            add_event_paths(&mut paths, events);
         }
 
-        // If enough time has passed since last reload
-        let now = Utc::now().timestamp_millis();
-        if last_compile + (MIN_RECOMPILE_INTERVAL as i64) > now {
+        // Classify changed files by source folder, skipping ignored files
+        let changes: Vec<_> = paths
+            .iter()
+            .filter_map(|path| classify_change(path, &watched_folders, filter))
+            .collect();
+
+        // Only errors or ignored files were received
+        if changes.is_empty() {
             continue;
         }
-        last_compile = now;
-
-        // Block thread for some time
-        // ? How can this be made better ?
-        thread::sleep(Duration::from_millis(FILE_SAVE_WAIT));
 
         // Run callback router
         // println_styles!("        Recompiling": Cyan + bold + dim);
-        router();
+        let rebuilt = router(&changes);
+
+        // Messages to send to every client
+        let messages = get_messages(&changes, &rebuilt);
 
         // Loop clients
         let clients = clients.lock().unwrap();
         for (_id, client) in clients.iter() {
-            // Send a reload request
-            client.send(Message::Text("reload".to_string()));
+            // Skip clients showing a route which was not rebuilt
+            if let (Rebuilt::Routes(routes), Some(path)) = (&rebuilt, &client.path) {
+                if !routes.iter().any(|route| route_matches_path(route, path)) {
+                    continue;
+                }
+            }
+
+            for message in &messages {
+                client.responder.send(Message::Text(message.clone()));
+            }
         }
     }
 }
+
+/// Add paths of debounced events to a set
+///
+/// Errors are ignored
+fn add_event_paths(paths: &mut HashSet<PathBuf>, events: DebounceEventResult) {
+    if let Ok(events) = events {
+        paths.extend(events.into_iter().map(|event| event.path));
+    }
+}
+
+/// Get the messages to send to clients, for a list of changes and the routes rebuilt
+///
+/// Returns a `style:<name>` message for each changed stylesheet, if only stylesheets were changed,
+/// or a single `style:*` message if a partial stylesheet was changed.
+/// Returns a single `reload:<routes>` message if only some routes were rebuilt.
+/// Otherwise returns a single `reload` message
+fn get_messages(changes: &[Change], rebuilt: &Rebuilt) -> Vec<String> {
+    // Reload if any non-style file was changed, or change could not be classified
+    if changes.is_empty() || changes.iter().any(|change| change.folder != Folder::Styles) {
+        return vec![match rebuilt {
+            Rebuilt::All => "reload".to_string(),
+            Rebuilt::Routes(routes) => format!("reload:{}", serde_json::json!(routes)),
+        }];
+    }
+
+    // Swap every stylesheet if a partial was changed, as it may be used by any stylesheet
+    if changes.iter().any(|change| is_partial(&change.name)) {
+        return vec!["style:*".to_string()];
+    }
+
+    let mut messages = Vec::new();
+    for change in changes {
+        let message = format!("style:{}", change.name);
+        if !messages.contains(&message) {
+            messages.push(message);
+        }
+    }
+    messages
+}
+
+/// Check if the path of a page refers to a route
+///
+/// Accepts '/foo', '/foo/', '/foo.html', and '/foo/index.html' patterns, matching the dev server router
+fn route_matches_path(route: &str, path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    let path = path
+        .strip_suffix("index.html")
+        .or_else(|| path.strip_suffix(".html"))
+        .unwrap_or(path);
+    path.trim_end_matches('/') == route.trim_matches('/')
+}
+
+/// Find which watched folder a changed file belongs to
+///
+/// Returns the folder kind, and the file name relative to the folder, without the file extension.
+/// Templates are named before the first `.` period, and stylesheets before the last, as when building
+/// Returns `None` if the file is not in any watched folder, or the file or a parent folder (inside the watched folder) is ignored
+fn classify_change(
+    path: &Path,
+    watched_folders: &[(Folder, PathBuf)],
+    filter: &FileFilter,
+) -> Option<Change> {
+    let path = absolute_path(path)?;
+
+    for (folder, root) in watched_folders {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+
+        // Check file and parent folders, until watched folder
+        if filter.is_ignored(&path, path.is_dir())
+            || path
+                .ancestors()
+                .skip(1)
+                .take_while(|parent| parent.starts_with(root) && parent != root)
+                .any(|parent| filter.is_ignored(parent, true))
+        {
+            return None;
+        }
+
+        // Split into parent folders and file name
+        let relative = relative.to_str()?.replace('\\', "/");
+        let (parent, name) = match relative.rsplit_once('/') {
+            Some((parent, name)) => (format!("{parent}/"), name),
+            None => (String::new(), relative.as_str()),
+        };
+
+        let name = match folder {
+            Folder::Templates => get_template_name(name),
+            _ => get_filename(name),
+        };
+        return Some(Change {
+            folder: *folder,
+            name: format!("{parent}{name}"),
+        });
+    }
+
+    None
+}
+
+/// Get absolute path of a file, which may have been removed
+///
+/// Canonicalizes the parent folder if the file no longer exists
+fn absolute_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = fs::canonicalize(path) {
+        return Some(path);
+    }
+    let parent = fs::canonicalize(path.parent()?).ok()?;
+    Some(parent.join(path.file_name()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_change_works() {
+        let styles = fs::canonicalize("tests/assets/styles").unwrap();
+        let templates = fs::canonicalize("tests/assets/templates").unwrap();
+        let folders = [(Folder::Templates, templates), (Folder::Styles, styles)];
+        let filter = FileFilter::empty();
+
+        assert_eq!(
+            classify_change(
+                Path::new("tests/assets/styles/scoped/stylish.scss"),
+                &folders,
+                &filter
+            ),
+            Some(Change {
+                folder: Folder::Styles,
+                name: "scoped/stylish".to_string(),
+            })
+        );
+        assert_eq!(
+            classify_change(
+                Path::new("tests/assets/templates/removed.hbs"),
+                &folders,
+                &filter
+            ),
+            Some(Change {
+                folder: Folder::Templates,
+                name: "removed".to_string(),
+            })
+        );
+        assert_eq!(
+            classify_change(
+                Path::new("tests/assets/styles/jquery.min.css"),
+                &folders,
+                &filter
+            ),
+            Some(Change {
+                folder: Folder::Styles,
+                name: "jquery.min".to_string(),
+            })
+        );
+        assert_eq!(
+            classify_change(
+                Path::new("tests/assets/templates/post.v2.hbs"),
+                &folders,
+                &filter
+            ),
+            Some(Change {
+                folder: Folder::Templates,
+                name: "post".to_string(),
+            })
+        );
+        assert_eq!(
+            classify_change(Path::new("src/lib.rs"), &folders, &filter),
+            None
+        );
+
+        let filter = FileFilter::new(&crate::Config::default()).unwrap();
+        assert_eq!(
+            classify_change(
+                Path::new("tests/assets/styles/global.scss.swp"),
+                &folders,
+                &filter
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn get_messages_works() {
+        let style = |name: &str| Change {
+            folder: Folder::Styles,
+            name: name.to_string(),
+        };
+
+        let template = Change {
+            folder: Folder::Templates,
+            name: "page".to_string(),
+        };
+
+        assert_eq!(get_messages(&[], &Rebuilt::All), ["reload"]);
+        assert_eq!(
+            get_messages(
+                &[style("global"), style("scoped/stylish"), style("global")],
+                &Rebuilt::All
+            ),
+            ["style:global", "style:scoped/stylish"]
+        );
+        assert_eq!(
+            get_messages(&[style("global"), style("scoped/_colors")], &Rebuilt::All),
+            ["style:*"]
+        );
+        assert_eq!(
+            get_messages(&[style("global"), template], &Rebuilt::All),
+            ["reload"]
+        );
+
+        let template = Change {
+            folder: Folder::Templates,
+            name: "other/article".to_string(),
+        };
+        assert_eq!(
+            get_messages(
+                &[template],
+                &Rebuilt::Routes(vec!["".to_string(), "blog/post".to_string()])
+            ),
+            [r#"reload:["","blog/post"]"#]
+        );
+    }
+
+    #[test]
+    fn route_matches_path_works() {
+        assert!(route_matches_path("", "/"));
+        assert!(route_matches_path("", "/index.html"));
+        assert!(route_matches_path("about", "/about"));
+        assert!(route_matches_path("about", "/about/"));
+        assert!(route_matches_path("about", "/about.html"));
+        assert!(route_matches_path("blog/post", "/blog/post/index.html"));
+
+        assert!(!route_matches_path("about", "/"));
+        assert!(!route_matches_path("blog/post", "/blog"));
+        assert!(!route_matches_path("", "/about"));
+    }
+}
@@ -0,0 +1,312 @@
+//! Snapshot testing for generated sites
+//!
+//! Only available with `"testing"` feature
+//!
+//! Build a site into memory, and compare every output file against snapshots committed in a folder.
+//! Run tests with the `UPDATE_SNAPSHOTS=1` environment variable to write new snapshots (and remove stale snapshots), after checking the changes are correct.
+//!
+//! Line endings are ignored when comparing text files.
+//! Disable `minify` in config for readable snapshots and diffs
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use unreact::prelude::*;
+//!
+//! #[test]
+//! fn site_snapshot() {
+//!     let config = Config {
+//!         minify: false,
+//!         ..Config::default()
+//!     };
+//!     let mut app = Unreact::new(config, false, "https://example.com").unwrap();
+//!     app.index("page", object! {}).unwrap();
+//!
+//!     // Compare with files in `tests/snapshots/site/`
+//!     unreact::testing::assert_snapshots(&mut app, "tests/snapshots/site");
+//! }
+//! ```
+
+use std::{fs, path::Path};
+
+use crate::{OutputMap, Unreact};
+
+/// Environment variable to update snapshots, instead of comparing, if set to `1` or `true`
+pub const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// Lines of unchanged context shown around changed lines in diffs
+const DIFF_CONTEXT: usize = 2;
+
+/// Build app into memory, and compare every output file against snapshots in a folder
+///
+/// See [`assert_output_snapshots`]
+///
+/// Panics if app could not be built, or any output file does not match its snapshot
+pub fn assert_snapshots(app: &mut Unreact, folder: impl AsRef<Path>) {
+    let output = match app.build_to_memory() {
+        Ok(output) => output,
+        Err(err) => panic!("Could not build app for snapshot: {err}"),
+    };
+    assert_output_snapshots(&output, folder);
+}
+
+/// Compare every output file against snapshots in a folder, or update snapshots if `UPDATE_SNAPSHOTS` is set
+///
+/// Snapshots are files in the folder at the same path as the output file, relative to the build directory.
+/// Output files without a snapshot, and snapshots without an output file, do not match
+///
+/// Panics if any output file does not match its snapshot, with a diff of each mismatched file
+pub fn assert_output_snapshots(output: &OutputMap, folder: impl AsRef<Path>) {
+    let folder = folder.as_ref();
+
+    if should_update() {
+        if let Err(err) = update_snapshots(output, folder) {
+            panic!(
+                "Could not update snapshots in '{}': {err}",
+                folder.display()
+            );
+        }
+        return;
+    }
+
+    let mismatches = compare_snapshots(output, folder);
+    if !mismatches.is_empty() {
+        panic!(
+            "{} file(s) do not match snapshots in '{}'\n\n{}\n\nRun with `{UPDATE_VAR}=1` to update snapshots",
+            mismatches.len(),
+            folder.display(),
+            mismatches.join("\n\n"),
+        );
+    }
+}
+
+/// Compare every output file against snapshots in a folder
+///
+/// Returns a message for each mismatched file, with a diff for text files
+pub fn compare_snapshots(output: &OutputMap, folder: impl AsRef<Path>) -> Vec<String> {
+    let folder = folder.as_ref();
+    let mut mismatches = Vec::new();
+
+    for (path, actual) in output {
+        let Ok(expected) = fs::read(folder.join(path)) else {
+            mismatches.push(format!("+++ {path} (new file, no snapshot)"));
+            continue;
+        };
+
+        match (std::str::from_utf8(&expected), std::str::from_utf8(actual)) {
+            // Text files, ignoring line endings
+            (Ok(expected), Ok(actual)) => {
+                let (expected, actual) =
+                    (expected.replace("\r\n", "\n"), actual.replace("\r\n", "\n"));
+                if expected != actual {
+                    mismatches.push(format!("~~~ {path}\n{}", diff(&expected, &actual)));
+                }
+            }
+            // Binary files
+            _ => {
+                if expected != *actual {
+                    mismatches.push(format!(
+                        "~~~ {path} (binary file, {} bytes in snapshot, {} bytes in output)",
+                        expected.len(),
+                        actual.len(),
+                    ));
+                }
+            }
+        }
+    }
+
+    // Snapshots without output file
+    for path in snapshot_files(folder, "") {
+        if !output.contains_key(&path) {
+            mismatches.push(format!("--- {path} (removed file, snapshot exists)"));
+        }
+    }
+
+    mismatches
+}
+
+/// Check if `UPDATE_SNAPSHOTS` environment variable is set to `1` or `true`
+fn should_update() -> bool {
+    std::env::var(UPDATE_VAR).is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+/// Write output files to snapshots folder, and remove snapshots without an output file
+///
+/// Only files in the snapshots folder are removed, never the folder itself.
+/// Snapshots which already match (ignoring line endings) are not rewritten
+fn update_snapshots(output: &OutputMap, folder: &Path) -> std::io::Result<()> {
+    for (path, content) in output {
+        let path = folder.join(path);
+        if fs::read(&path).is_ok_and(|expected| is_same(&expected, content)) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+
+    // Stale snapshots
+    for path in snapshot_files(folder, "") {
+        if output.contains_key(&path) {
+            continue;
+        }
+        fs::remove_file(folder.join(&path))?;
+
+        // Remove parent folders inside snapshots folder, if now empty
+        let mut parent = Path::new(&path).parent();
+        while let Some(dir) = parent.filter(|dir| !dir.as_os_str().is_empty()) {
+            if fs::remove_dir(folder.join(dir)).is_err() {
+                break;
+            }
+            parent = dir.parent();
+        }
+    }
+    Ok(())
+}
+
+/// Check if a snapshot matches an output file, ignoring line endings of text files
+fn is_same(expected: &[u8], actual: &[u8]) -> bool {
+    match (std::str::from_utf8(expected), std::str::from_utf8(actual)) {
+        (Ok(expected), Ok(actual)) => {
+            expected.replace("\r\n", "\n") == actual.replace("\r\n", "\n")
+        }
+        _ => expected == actual,
+    }
+}
+
+/// Get paths of every file in snapshots folder recursively, relative to the folder
+///
+/// Returns an empty list if the folder does not exist
+fn snapshot_files(folder: &Path, parent: &str) -> Vec<String> {
+    let Ok(children) = fs::read_dir(folder.join(parent)) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for child in children.flatten() {
+        let Some(name) = child.file_name().to_str().map(String::from) else {
+            continue;
+        };
+        let path = format!("{parent}{name}");
+
+        if child.path().is_dir() {
+            files.extend(snapshot_files(folder, &format!("{path}/")));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Create a line diff of two texts, with `-` for lines only in `expected`, and `+` for lines only in `actual`
+///
+/// Only changed lines, and some lines of context around them, are included
+fn diff(expected: &str, actual: &str) -> String {
+    let (old, new): (Vec<_>, Vec<_>) = (expected.lines().collect(), actual.lines().collect());
+
+    // Longest common subsequence lengths, for every suffix of both texts
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    // Walk both texts, marking each line
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    // Only keep changed lines, with context
+    let is_near_change = |index: usize| {
+        let start = index.saturating_sub(DIFF_CONTEXT);
+        let end = (index + DIFF_CONTEXT + 1).min(lines.len());
+        lines[start..end].iter().any(|(mark, _)| *mark != ' ')
+    };
+
+    let mut output = Vec::new();
+    let mut skipped = false;
+    for (index, (mark, line)) in lines.iter().enumerate() {
+        if !is_near_change(index) {
+            skipped = true;
+            continue;
+        }
+        if skipped && !output.is_empty() {
+            output.push("  ...".to_string());
+        }
+        skipped = false;
+        output.push(format!("{mark} {line}"));
+    }
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_works() {
+        let expected = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk";
+        let actual = "a\nb\nc\nD\ne\nf\ng\nh\ni\nj\nk\nl";
+
+        assert_eq!(
+            diff(expected, actual),
+            "  b\n  c\n- d\n+ D\n  e\n  f\n  ...\n  j\n  k\n+ l"
+        );
+        assert_eq!(diff("same", "same"), "");
+    }
+
+    #[test]
+    fn compare_snapshots_works() {
+        // Unique folder, so tests running at the same time do not conflict
+        let folder =
+            &std::env::temp_dir().join(format!("unreact-snapshots-test-{}", std::process::id()));
+        let mut output = OutputMap::new();
+        output.insert("index.html".to_string(), b"<h1>Hello</h1>\r\n".to_vec());
+        output.insert("public/image.png".to_string(), vec![0xff, 0xd8]);
+
+        update_snapshots(&output, folder).unwrap();
+        assert!(compare_snapshots(&output, folder).is_empty());
+
+        // Line endings are ignored
+        output.insert("index.html".to_string(), b"<h1>Hello</h1>\n".to_vec());
+        assert!(compare_snapshots(&output, folder).is_empty());
+
+        output.insert("index.html".to_string(), b"<h1>World</h1>\n".to_vec());
+        output.remove("public/image.png");
+        output.insert("about/index.html".to_string(), Vec::new());
+
+        assert_eq!(
+            compare_snapshots(&output, folder),
+            [
+                "+++ about/index.html (new file, no snapshot)",
+                "~~~ index.html\n- <h1>Hello</h1>\n+ <h1>World</h1>",
+                "--- public/image.png (removed file, snapshot exists)",
+            ]
+        );
+
+        // Only stale snapshots (and their empty folders) are removed
+        update_snapshots(&output, folder).unwrap();
+        assert!(compare_snapshots(&output, folder).is_empty());
+        assert!(!folder.join("public").exists());
+        assert!(folder.join("about/index.html").exists());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use unreact::prelude::*;
 
-#[test]
-fn large_example() {
+/// Create app with routes
+fn create_app() -> Unreact<'static> {
     let config = Config {
         strict: true,
         build: "tests/build".to_string(),
@@ -15,7 +15,8 @@ fn large_example() {
 
     app.globalize(object! {
         smiley: "(^_^)"
-    });
+    })
+    .expect("Could not set globals");
 
     app.index("page", object! {message: "World!"})
         .expect("Could not create index route")
@@ -25,5 +26,16 @@ fn large_example() {
         .route("article", "other/article", object! {})
         .expect("Could not create custom route");
 
-    app.run().expect("Could not compile");
+    app
+}
+
+#[test]
+fn large_example() {
+    create_app().run().expect("Could not compile");
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn large_snapshot() {
+    unreact::testing::assert_snapshots(&mut create_app(), "tests/snapshots/large");
 }
@@ -0,0 +1,595 @@
+use std::{cell::Cell, rc::Rc};
+
+use serde::Serialize;
+use unreact::{prelude::*, LinkCheck, OutputMap};
+
+#[test]
+fn memory_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<html><head>{{> CSS name=\"global\"}}</head><body>{{message}}</body></html>",
+        )
+        .file("assets/styles/_colors.scss", "$text: white;")
+        .file(
+            "assets/styles/global.scss",
+            "@use 'colors';\nbody { color: colors.$text; }",
+        )
+        .file("assets/public/robots.txt", "User-agent: *");
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route")
+        .route_raw("hello", "this is my hello page");
+
+    let output = app.build_to_memory().expect("Could not compile");
+
+    let mut paths: Vec<_> = output.keys().collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "hello/index.html",
+            "index.html",
+            "public/robots.txt",
+            "styles/global/style.css",
+        ]
+    );
+
+    let index = String::from_utf8_lossy(&output["index.html"]);
+    assert!(index.contains("<body>World!"));
+    assert!(index.contains("https://example.com/styles/global/style.css"));
+    assert_eq!(output["styles/global/style.css"], b"body{color:white}");
+    assert_eq!(output["public/robots.txt"], b"User-agent: *");
+
+    // Template does not exist in memory
+    assert!(matches!(
+        app.route("missing", "missing", object! {}),
+        Err(Error::TemplateNotExist(name)) if name == "missing",
+    ));
+}
+
+#[test]
+fn duplicate_style_example() {
+    let source = Source::memory()
+        .file("assets/templates/page.hbs", "{{message}}")
+        .file("assets/styles/global.scss", "body { color: white; }")
+        .file("assets/styles/global.css", "body { color: black; }");
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route");
+
+    assert!(matches!(
+        app.build_to_memory(),
+        Err(Error::DuplicateStyle(name, first, second))
+            if name == "global" && first == "global.css" && second == "global.scss",
+    ));
+}
+
+#[test]
+fn dotted_style_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<html><head>{{> CSS name=\"jquery.min\"}}</head><body></body></html>",
+        )
+        .file("assets/styles/jquery.min.css", "body { color: white; }")
+        .file("assets/styles/theme.dark.scss", "body { color: black; }")
+        .file("assets/styles/theme.light.scss", "body { color: white; }");
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+    app.index("page", object! {})
+        .expect("Could not create index route");
+
+    let output = app.build_to_memory().expect("Could not compile");
+
+    assert_eq!(output["styles/jquery.min/style.css"], b"body{color:white}");
+    assert_eq!(output["styles/theme.dark/style.css"], b"body{color:black}");
+    assert_eq!(output["styles/theme.light/style.css"], b"body{color:white}");
+
+    let index = String::from_utf8_lossy(&output["index.html"]);
+    assert!(index.contains("https://example.com/styles/jquery.min/style.css"));
+}
+
+#[test]
+fn style_output_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<head>{{> CSS name=\"scoped/indented\"}}</head><img src=\"{{URL}}{{PUBLIC \"image.png\"}}\">",
+        )
+        .file("assets/styles/scoped/indented.sass", "body\n  color: white\n")
+        .file("assets/public/image.png", "png");
+
+    let config = Config {
+        style_output: "{name}.css".to_string(),
+        build_styles: "assets/css".to_string(),
+        build_public: "assets/static".to_string(),
+        ..Config::default()
+    };
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.index("page", object! {})
+        .expect("Could not create index route");
+
+    let output = app.build_to_memory().expect("Could not compile");
+
+    let mut paths: Vec<_> = output.keys().collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "assets/css/scoped/indented.css",
+            "assets/static/image.png",
+            "index.html",
+        ]
+    );
+
+    let index = String::from_utf8_lossy(&output["index.html"]);
+    assert!(index.contains("https://example.com/assets/css/scoped/indented.css"));
+    assert!(index.contains("https://example.com/assets/static/image.png"));
+    assert_eq!(
+        output["assets/css/scoped/indented.css"],
+        b"body{color:white}"
+    );
+}
+
+#[test]
+fn purge_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<head>{{> CSS name=\"global\"}}</head><p class=\"used\">{{message}}</p>",
+        )
+        .file(
+            "assets/styles/global.scss",
+            ".used { color: red; } .unused { color: blue; } .js-open { color: green; }",
+        );
+
+    let config = Config {
+        purge: true,
+        purge_safelist: vec!["js-*".to_string()],
+        ..Config::default()
+    };
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route");
+
+    let output = app.build_to_memory().expect("Could not compile");
+    assert_eq!(
+        output["styles/global/style.css"],
+        b".used{color:red}.js-open{color:green}"
+    );
+}
+
+#[test]
+fn critical_css_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<head>{{> CSS name=\"global\"}}</head><p>{{message}}</p>",
+        )
+        .file(
+            "assets/styles/global.scss",
+            "p { color: red; } .unused { color: blue; }",
+        );
+
+    let config = Config {
+        critical_css: true,
+        ..Config::default()
+    };
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route");
+
+    let output = app.build_to_memory().expect("Could not compile");
+
+    // Only used rules are inlined, and full stylesheet is preloaded
+    let index = String::from_utf8_lossy(&output["index.html"]);
+    assert!(index.contains("<style>p{color:red}</style>"));
+    assert!(!index.contains("unreact:critical"));
+    assert!(index.contains("rel=preload"));
+    assert_eq!(
+        output["styles/global/style.css"],
+        b"p{color:red}.unused{color:blue}"
+    );
+}
+
+#[test]
+fn render_example() {
+    let source = Source::memory()
+        .file("assets/templates/page.hbs", "{{message}} {{GLOBAL.smiley}}")
+        .file("assets/templates/hello.hbs", "Hello {{world}}");
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+
+    app.globalize(object! {
+        smiley: "(^_^)"
+    })
+    .expect("Could not set globals");
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route")
+        .route_builder(|app| {
+            app.route("built", "hello", object! { world: "Builder" })?;
+            Ok(())
+        });
+
+    assert_eq!(app.render("").unwrap(), "World! (^_^)");
+    assert_eq!(app.render("/").unwrap(), "World! (^_^)");
+    assert_eq!(app.render("/built/").unwrap(), "Hello Builder");
+    assert!(matches!(
+        app.render("missing"),
+        Err(Error::RouteNotExist(path)) if path == "missing",
+    ));
+}
+
+#[test]
+fn render_critical_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<head>{{> CSS name=\"global\"}}</head><p>{{message}}</p>",
+        )
+        .file("assets/styles/global.scss", "p { color: red; }");
+
+    let config = Config {
+        critical_css: true,
+        ..Config::default()
+    };
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route");
+
+    // Styles are not compiled, so only the full stylesheet is loaded
+    let page = app.render("").expect("Could not render");
+    assert!(!page.contains("unreact:critical"));
+    assert!(!page.contains("<style>"));
+    assert!(page.contains("https://example.com/styles/global/style.css"));
+    assert!(page.contains("<p>World!</p>"));
+}
+
+#[test]
+fn route_builder_example() {
+    let source = Source::memory().file("assets/templates/page.hbs", "{{message}}");
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+
+    let runs = Rc::new(Cell::new(0));
+    let builder_runs = Rc::clone(&runs);
+    app.route_raw("", "index")
+        .route_raw("shared", "static")
+        .route_builder(move |app| {
+            builder_runs.set(builder_runs.get() + 1);
+            let run = builder_runs.get();
+            app.route_raw(&format!("run{run}"), "built")
+                .route_raw("shared", format!("built {run}"));
+            Ok(())
+        });
+
+    let routes = |output: &OutputMap| output.keys().cloned().collect::<Vec<_>>();
+
+    let output = app.build_to_memory().expect("Could not compile");
+    assert_eq!(
+        routes(&output),
+        ["index.html", "run1/index.html", "shared/index.html"]
+    );
+    assert_eq!(output["shared/index.html"], b"built 1");
+
+    // Route added after builder has ran is kept, and routes from previous run are removed
+    app.route_raw("added", "added");
+    let output = app.build_to_memory().expect("Could not compile");
+    assert_eq!(
+        routes(&output),
+        [
+            "added/index.html",
+            "index.html",
+            "run2/index.html",
+            "shared/index.html",
+        ]
+    );
+    assert_eq!(output["shared/index.html"], b"built 2");
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+fn serialize_example() {
+    #[derive(Serialize)]
+    struct Post {
+        title: String,
+        tags: Vec<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    struct Site {
+        name: &'static str,
+    }
+
+    let source = Source::memory().file(
+        "assets/templates/post.hbs",
+        "{{GLOBAL.name}}: {{title}}{{#each tags}} #{{this}}{{/each}}",
+    );
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+
+    app.globalize(Site { name: "Blog" })
+        .expect("Could not set globals")
+        .route(
+            "post",
+            "post",
+            Post {
+                title: "Hello".to_string(),
+                tags: vec!["rust", "web"],
+            },
+        )
+        .expect("Could not create post route");
+
+    assert_eq!(app.render("post").unwrap(), "Blog: Hello #rust #web");
+
+    // Data must be an object
+    assert!(matches!(
+        app.route("list", "post", ["a", "b"]),
+        Err(Error::InvalidData(name, message)) if name == "route 'list'" && message.contains("an array"),
+    ));
+    assert!(matches!(
+        app.globalize("smiley"),
+        Err(Error::InvalidData(name, _)) if name == "globals",
+    ));
+}
+
+#[test]
+fn globals_example() {
+    let source = Source::memory().file(
+        "assets/templates/page.hbs",
+        "{{GLOBAL.site.name}} {{GLOBAL.site.author}} {{GLOBAL.sidebar}}",
+    );
+
+    let mut app = Unreact::with_source(Config::default(), "https://example.com", source)
+        .expect("Could not create app");
+
+    // Separate parts of app can each add globals
+    app.globalize(object! {site: object! {name: "Blog"}, sidebar: "main"})
+        .expect("Could not set globals")
+        .merge_globals(object! {site: object! {author: "Me"}})
+        .expect("Could not merge globals")
+        .scope_globals("docs", object! {sidebar: "docs"})
+        .expect("Could not scope globals")
+        .scope_globals("/docs/api/", object! {sidebar: "api"})
+        .expect("Could not scope globals");
+
+    app.index("page", object! {})
+        .expect("Could not create index route")
+        .route("docs", "page", object! {})
+        .expect("Could not create docs route")
+        .route("docs/api/fn", "page", object! {})
+        .expect("Could not create api route")
+        .route("docs-old", "page", object! {})
+        .expect("Could not create old docs route")
+        .route(
+            "docs/legacy",
+            "page",
+            object! {GLOBAL: object! {site: object! {author: "Someone"}, sidebar: "legacy"}},
+        )
+        .expect("Could not create legacy route");
+
+    // Global < scoped (shorter prefix first) < route data
+    assert_eq!(app.render("").unwrap(), "Blog Me main");
+    assert_eq!(app.render("docs").unwrap(), "Blog Me docs");
+    assert_eq!(app.render("docs/api/fn").unwrap(), "Blog Me api");
+    assert_eq!(app.render("docs-old").unwrap(), "Blog Me main");
+    assert_eq!(app.render("docs/legacy").unwrap(), "Blog Someone legacy");
+
+    // Replacing globals keeps scoped globals
+    app.globalize(object! {site: object! {name: "News"}})
+        .expect("Could not set globals")
+        .set_global("sidebar", "none")
+        .expect("Could not set global");
+    assert_eq!(app.render("").unwrap(), "News none");
+    assert_eq!(app.render("docs").unwrap(), "News docs");
+}
+
+#[test]
+fn report_example() {
+    // Unique folder, so tests running at the same time do not conflict
+    let folder = std::env::temp_dir().join(format!("unreact-report-test-{}", std::process::id()));
+    let folder = folder.to_str().unwrap();
+
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<head>{{> CSS name=\"global\"}}</head><p>  {{message}}  </p><a href=\"/hello\">Hello</a>",
+        )
+        .file("assets/styles/global.scss", "p { color: red; }")
+        .file("assets/styles/empty.scss", "")
+        .file("assets/public/robots.txt", "User-agent: *");
+
+    let config = Config {
+        build: format!("{folder}/build"),
+        report: format!("{folder}/report.json"),
+        // Empty stylesheet is still empty with source map comment
+        source_maps: true,
+        // Size is measured after page is changed
+        critical_css: true,
+        base_path: "docs".to_string(),
+        ..Config::default()
+    };
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.route("about", "page", object! {message: "World!"})
+        .expect("Could not create about route")
+        .route_raw("hello", "this is my hello page");
+
+    let report = app.build().expect("Could not compile");
+
+    let paths: Vec<_> = report.routes.iter().map(|route| &route.path).collect();
+    assert_eq!(paths, ["about", "hello"]);
+    let about = &report.routes[0];
+    assert_eq!(about.output, "about/index.html");
+    assert_eq!(about.template.as_deref(), Some("page"));
+    assert_eq!(
+        about.size,
+        std::fs::metadata(format!("{folder}/build/about/index.html"))
+            .expect("Could not read page")
+            .len() as usize
+    );
+
+    let styles: Vec<_> = report.styles.iter().map(|style| &style.name).collect();
+    assert_eq!(styles, ["empty", "global"]);
+    assert_eq!(report.public[0].output, "public/robots.txt");
+    // Pages, styles with source maps, and public file
+    assert_eq!(report.files, 7);
+    assert_eq!(
+        report.warnings,
+        ["Stylesheet 'empty' is empty", "No index route"]
+    );
+
+    let json =
+        std::fs::read_to_string(format!("{folder}/report.json")).expect("Could not read report");
+    assert!(json.contains("\"render_ms\""));
+
+    std::fs::remove_dir_all(folder).expect("Could not remove folder");
+}
+
+#[test]
+fn source_map_example() {
+    // Unique folder, so tests running at the same time do not conflict
+    let folder =
+        std::env::temp_dir().join(format!("unreact-source-map-test-{}", std::process::id()));
+    let folder = folder.to_str().unwrap();
+
+    let create_app = |approximate_source_maps| {
+        let source = Source::memory()
+            .file("assets/templates/page.hbs", "{{message}}")
+            .file("assets/styles/global.scss", "body {\n  color: white;\n}\n");
+        let config = Config {
+            build: format!("{folder}/build"),
+            source_maps: true,
+            approximate_source_maps,
+            ..Config::default()
+        };
+        let mut app = Unreact::with_source(config, "https://example.com", source)
+            .expect("Could not create app");
+        app.index("page", object! {message: "World!"})
+            .expect("Could not create index route");
+        app
+    };
+    let mappings = |output: &OutputMap| {
+        let map = String::from_utf8_lossy(&output["styles/global/style.css.map"]).to_string();
+        assert!(map.contains("assets/styles/global.scss\"]"));
+        map.contains("\"mappings\":\"\"")
+    };
+
+    // Only source file, without mappings
+    let output = create_app(false)
+        .build_to_memory()
+        .expect("Could not compile");
+    assert!(mappings(&output));
+
+    let output = create_app(true)
+        .build_to_memory()
+        .expect("Could not compile");
+    assert!(!mappings(&output));
+
+    let report = create_app(true).build().expect("Could not compile");
+    assert_eq!(
+        report.warnings,
+        ["Source maps for styles have approximate mappings"]
+    );
+
+    std::fs::remove_dir_all(folder).expect("Could not remove folder");
+}
+
+#[test]
+fn relative_urls_example() {
+    let source = Source::memory().file(
+        "assets/templates/page.hbs",
+        "<a href=\"{{path \"\"}}\">Home</a><a href=\"{{path \"blog/post\"}}\">Post</a>",
+    );
+
+    let config = Config {
+        relative_urls: true,
+        check_links: LinkCheck::Error,
+        ..Config::default()
+    };
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.index("page", object! {})
+        .expect("Could not create index route")
+        .route("blog/post", "page", object! {})
+        .expect("Could not create post route");
+
+    // Links to folder routes include `index.html`, for `file://`
+    let output = app.build_to_memory().expect("Could not compile");
+    let index = String::from_utf8_lossy(&output["index.html"]);
+    assert!(index.contains("href=./index.html>"));
+    assert!(index.contains("href=./blog/post/index.html>"));
+    let post = String::from_utf8_lossy(&output["blog/post/index.html"]);
+    assert!(post.contains("href=../../index.html>"));
+    assert!(post.contains("href=../../blog/post/index.html>"));
+}
+
+#[test]
+fn check_links_example() {
+    let source = Source::memory()
+        .file(
+            "assets/templates/page.hbs",
+            "<a href=\"{{URL}}about\">About</a><a href=\"{{path \"contact\"}}\">Contact</a>",
+        )
+        .file("assets/templates/about.hbs", "<h1 id=\"about\">About</h1>");
+
+    let create_app = |check_links| {
+        let config = Config {
+            check_links,
+            ..Config::default()
+        };
+        let mut app = Unreact::with_source(config, "https://example.com", source.clone())
+            .expect("Could not create app");
+        app.index("page", object! {})
+            .expect("Could not create index route")
+            .route("about", "about", object! {})
+            .expect("Could not create about route");
+        app
+    };
+
+    assert!(matches!(
+        create_app(LinkCheck::Error).build_to_memory(),
+        Err(Error::BrokenLinks(links)) if links == ["Broken link '/contact' in 'index.html'"],
+    ));
+    assert!(create_app(LinkCheck::Warn).build_to_memory().is_ok());
+    assert!(create_app(LinkCheck::Off).build_to_memory().is_ok());
+}
+
+#[test]
+#[cfg(feature = "include_dir")]
+fn include_dir_example() {
+    static ASSETS: include_dir::Dir = include_dir::include_dir!("$CARGO_MANIFEST_DIR/tests/assets");
+
+    let config = Config {
+        templates: "tests/assets/templates".to_string(),
+        styles: "tests/assets/styles".to_string(),
+        public: "tests/assets/public".to_string(),
+        ..Config::default()
+    };
+    let source = Source::memory().include_dir("tests/assets", &ASSETS);
+
+    let mut app =
+        Unreact::with_source(config, "https://example.com", source).expect("Could not create app");
+    app.index("page", object! {message: "World!"})
+        .expect("Could not create index route");
+
+    let output = app.build_to_memory().expect("Could not compile");
+    assert!(output.contains_key("index.html"));
+    assert!(output.contains_key("styles/scoped/stylish/style.css"));
+    assert!(output.contains_key("public/b/c.txt"));
+    assert!(!output.contains_key("public/a.txt.swp"));
+}
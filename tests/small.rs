@@ -1,7 +1,7 @@
 use unreact::prelude::*;
 
-#[test]
-fn small_example() {
+/// Create app with routes
+fn create_app() -> Unreact<'static> {
     let config = Config {
         build: "tests/build".to_string(),
         templates: "tests/assets/templates".to_string(),
@@ -15,5 +15,16 @@ fn small_example() {
     app.index("page", object! {})
         .expect("Could not create route");
 
-    app.run().expect("Could not compile");
+    app
+}
+
+#[test]
+fn small_example() {
+    create_app().run().expect("Could not compile");
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn small_snapshot() {
+    unreact::testing::assert_snapshots(&mut create_app(), "tests/snapshots/small");
 }